@@ -0,0 +1,188 @@
+//! String encodings used by `#[derive(Cacheable)]` for fields that don't fit in a single
+//! `ToString`/`FromStr` value.
+//!
+//! Collections are stored as a sequence of length-prefixed items (`<byte length>:<item>`), so
+//! items may contain any character, including `:` and digits, and round-trip exactly.
+//! `["a:b", ""]` is stored as `3:a:b0:`.
+//! Nested `Cacheable` objects are stored as the encoded map of their own fields, which is also how
+//! the derive stores fields without an attribute whose type doesn't implement `ToString` and `FromStr`.
+//!
+//! Fields using `#[cache(with = "path")]` are encoded by `path::encode` and decoded by `path::decode`,
+//! which can be module functions or a type implementing `Codec`. Module `encode` functions return either
+//! a `String` or, if encoding can fail, a `Result<String>` (see `Encoded`).
+
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use crate::CacheError;
+use crate::Cacheable;
+use crate::Result;
+
+/// A string encoding for values whose `ToString`/`FromStr` don't round-trip, usable from the derive
+/// with `#[cache(with = "YourCodec")]` and from hand-written `Cacheable` impls.
+/// Values `encode` fails on are reported by the cache writing them.
+pub trait Codec<T> {
+    fn encode(value: &T) -> Result<String>;
+    fn decode(raw: &str) -> Result<T>;
+}
+
+/// What the `encode` function of a `#[cache(with = "path")]` field returns.
+pub trait Encoded {
+    fn into_result(self) -> Result<String>;
+}
+
+impl Encoded for String {
+    fn into_result(self) -> Result<String> {
+        Ok(self)
+    }
+}
+
+impl Encoded for Result<String> {
+    fn into_result(self) -> Result<String> {
+        self
+    }
+}
+
+/// Lowercase hexadecimal encoding of bytes.
+pub struct Hex;
+
+impl Codec<Vec<u8>> for Hex {
+    fn encode(value: &Vec<u8>) -> Result<String> {
+        Ok(value.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    fn decode(raw: &str) -> Result<Vec<u8>> {
+        if !raw.len().is_multiple_of(2) || !raw.is_ascii() {
+            return Err(CacheError::Other(format!("Invalid hex value {:?}", raw)));
+        }
+
+        (0..raw.len()).step_by(2).map(|i| {
+            u8::from_str_radix(&raw[i..i + 2], 16).map_err(|_| CacheError::Other(format!("Invalid hex value {:?}", raw)))
+        }).collect()
+    }
+}
+
+fn push_item(out: &mut String, item: &str) {
+    out.push_str(&item.len().to_string());
+    out.push(':');
+    out.push_str(item);
+}
+
+fn next_item<'a>(raw: &mut &'a str) -> Result<&'a str> {
+    let sep = match raw.find(':') {
+        Some(sep) => sep,
+        None => return Err(CacheError::Other(format!("Missing length prefix in encoded value {:?}", raw))),
+    };
+
+    let len: usize = match raw[..sep].parse() {
+        Ok(len) => len,
+        Err(_) => return Err(CacheError::Other(format!("Invalid length prefix in encoded value {:?}", raw))),
+    };
+
+    let rest = &raw[sep + 1..];
+    if rest.len() < len || !rest.is_char_boundary(len) {
+        return Err(CacheError::Other(format!("Truncated item in encoded value {:?}", raw)));
+    }
+
+    let (item, rest) = rest.split_at(len);
+    *raw = rest;
+    Ok(item)
+}
+
+fn parse_item<T: FromStr>(item: &str) -> Result<T> {
+    T::from_str(item).map_err(|_| CacheError::Other(format!("Unable to parse encoded item {:?}", item)))
+}
+
+/// Encodes a sequence (`Vec`, `VecDeque`, `HashSet`, `BTreeSet`, ...) into a single string.
+pub fn encode_seq<'a, I, T>(items: I) -> String
+    where I: IntoIterator<Item = &'a T>, T: ToString + 'a {
+    let mut out = String::new();
+    for item in items {
+        push_item(&mut out, &item.to_string());
+    }
+    out
+}
+
+/// Decodes a string produced by `encode_seq`.
+pub fn decode_seq<C, T>(mut raw: &str) -> Result<C>
+    where C: FromIterator<T>, T: FromStr {
+    let mut items = Vec::new();
+    while !raw.is_empty() {
+        items.push(parse_item(next_item(&mut raw)?)?);
+    }
+    Ok(items.into_iter().collect())
+}
+
+/// Encodes a map (`HashMap`, `BTreeMap`, ...) into a single string, as alternating keys and values.
+pub fn encode_map<'a, I, K, V>(entries: I) -> String
+    where I: IntoIterator<Item = (&'a K, &'a V)>, K: ToString + 'a, V: ToString + 'a {
+    let mut out = String::new();
+    for (k, v) in entries {
+        push_item(&mut out, &k.to_string());
+        push_item(&mut out, &v.to_string());
+    }
+    out
+}
+
+/// Decodes a string produced by `encode_map`.
+pub fn decode_map<C, K, V>(mut raw: &str) -> Result<C>
+    where C: FromIterator<(K, V)>, K: FromStr, V: FromStr {
+    let mut entries = Vec::new();
+    while !raw.is_empty() {
+        let k = parse_item(next_item(&mut raw)?)?;
+        let v = parse_item(next_item(&mut raw)?)?;
+        entries.push((k, v));
+    }
+    Ok(entries.into_iter().collect())
+}
+
+/// Encodes a whole `Cacheable` object into a single string, as the encoded map of its fields.
+pub fn encode_object<O: Cacheable>(obj: &O) -> Result<String> {
+    let fields = obj.try_to_redis_obj()?;
+    Ok(encode_map(fields.iter().map(|(k, v)| (k, v))))
+}
+
+/// Decodes a string produced by `encode_object`.
+pub fn decode_object<O: Cacheable>(raw: &str) -> Result<O> {
+    O::from_redis_obj(decode_map(raw)?)
+}
+
+// Encoding of the derived fields without an attribute, picked by autoref-based method resolution:
+// `(&Field::<T>(PhantomData)).encode_field(..)` finds `PlainField` on `&Field<T>` first when `T` implements
+// `ToString` and `FromStr`, and falls back to `ObjectField` on `&&Field<T>` when `T` is `Cacheable`.
+// Bounds on type parameters are only known as written, so generic fields keep `ToString`/`FromStr`.
+
+#[doc(hidden)]
+pub struct Field<T>(pub PhantomData<T>);
+
+#[doc(hidden)]
+pub trait PlainField<T> {
+    fn encode_field(&self, value: &T) -> Result<String>;
+    fn decode_field(&self, raw: &str) -> Result<T>;
+}
+
+impl<T: ToString + FromStr> PlainField<T> for Field<T> {
+    fn encode_field(&self, value: &T) -> Result<String> {
+        Ok(value.to_string())
+    }
+
+    fn decode_field(&self, raw: &str) -> Result<T> {
+        parse_item(raw)
+    }
+}
+
+#[doc(hidden)]
+pub trait ObjectField<T> {
+    fn encode_field(&self, value: &T) -> Result<String>;
+    fn decode_field(&self, raw: &str) -> Result<T>;
+}
+
+impl<T: Cacheable> ObjectField<T> for &Field<T> {
+    fn encode_field(&self, value: &T) -> Result<String> {
+        encode_object(value)
+    }
+
+    fn decode_field(&self, raw: &str) -> Result<T> {
+        decode_object(raw)
+    }
+}
@@ -0,0 +1,96 @@
+//! Optional compression of large cached values, enabled with `Cache::with_compression`.
+//!
+//! The algorithms are behind the `lz4` and `zstd` features. Values at least `threshold` bytes long are
+//! stored as a `0xFF` byte, an algorithm id and the compressed bytes. `0xFF` never appears in UTF-8,
+//! so uncompressed values, including everything written before compression was turned on, are still
+//! read as is. Compressed values are always detected on read, whatever the cache is configured with.
+
+use crate::CacheError;
+use crate::Result;
+
+const HEADER: u8 = 0xFF;
+#[cfg(feature = "lz4")]
+const LZ4_ID: u8 = b'4';
+#[cfg(feature = "zstd")]
+const ZSTD_ID: u8 = b'z';
+
+/// Compression algorithms, each available with the cargo feature of the same name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// lz4 block format, fast with a moderate ratio.
+    #[cfg(feature = "lz4")]
+    Lz4,
+    /// zstd at the given level (0 for the default level), slower with a better ratio.
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+/// Which values the cache compresses, and how.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Compression {
+    pub algorithm: Algorithm,
+    /// Size in bytes from which values are compressed, smaller ones are stored as is.
+    pub threshold: usize,
+}
+
+impl Compression {
+    pub fn new(algorithm: Algorithm, threshold: usize) -> Self {
+        Compression {
+            algorithm,
+            threshold,
+        }
+    }
+
+    /// `value` as stored, compressed if it reaches the threshold.
+    pub(crate) fn compress(&self, value: String) -> Vec<u8> {
+        if value.len() < self.threshold {
+            return value.into_bytes();
+        }
+
+        match self.algorithm {
+            #[cfg(feature = "lz4")]
+            Algorithm::Lz4 => framed(LZ4_ID, lz4_flex::compress_prepend_size(value.as_bytes())),
+            #[cfg(feature = "zstd")]
+            Algorithm::Zstd(level) => match zstd::encode_all(value.as_bytes(), level) {
+                Ok(compressed) => framed(ZSTD_ID, compressed),
+                Err(_) => value.into_bytes(),
+            },
+        }
+    }
+}
+
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+fn framed(id: u8, compressed: Vec<u8>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(compressed.len() + 2);
+    out.push(HEADER);
+    out.push(id);
+    out.extend(compressed);
+    out
+}
+
+/// Compresses `value` if `compression` is set.
+pub(crate) fn compress(compression: Option<&Compression>, value: String) -> Vec<u8> {
+    match compression {
+        Some(compression) => compression.compress(value),
+        None => value.into_bytes(),
+    }
+}
+
+/// Reads a value written by `compress`, compressed or not.
+pub(crate) fn decompress(raw: Vec<u8>) -> Result<String> {
+    let bytes = if raw.first() == Some(&HEADER) {
+        match (raw.get(1), &raw[2.min(raw.len())..]) {
+            #[cfg(feature = "lz4")]
+            (Some(&LZ4_ID), compressed) => lz4_flex::decompress_size_prepended(compressed)
+                .map_err(|e| CacheError::Other(format!("Unable to decompress lz4 value: {}", e)))?,
+            #[cfg(feature = "zstd")]
+            (Some(&ZSTD_ID), compressed) => zstd::decode_all(compressed)
+                .map_err(|e| CacheError::Other(format!("Unable to decompress zstd value: {}", e)))?,
+            (id, _) => return Err(CacheError::Other(format!("Value compressed with an unknown or disabled algorithm {:?}", id.map(|&id| id as char)))),
+        }
+    } else {
+        raw
+    };
+
+    String::from_utf8(bytes).map_err(|e| CacheError::Other(format!("Cached value isn't valid UTF-8: {}", e)))
+}
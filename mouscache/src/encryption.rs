@@ -0,0 +1,165 @@
+//! Client-side encryption of the objects written by the redis cache, enabled with `Cache::with_encryption`
+//! and the `encryption` feature. Only objects are encrypted: the values given to the raw hash and set functions
+//! of `CacheFunc` (`hash_set`, `hash_multiple_set`, `set_add`, the `_bytes` variants...) are stored in clear,
+//! and keys are never encrypted.
+//!
+//! Each value is encrypted with AES-256-GCM under the current key of a `KeyProvider`, and stored as a `0xFE`
+//! byte, a format id, the key id (4 bytes, big-endian), a random 12-byte nonce and the ciphertext.
+//! The redis key and hash field are authenticated along with it, so values can't be moved to another entry.
+//! Keys are rotated by making a new key current while keeping the older ones readable.
+//!
+//! Entries that fail authentication, or whose key is unknown, are read as `CacheError::DecryptionError`,
+//! as are values stored in clear unless `Cache::allow_plaintext_reads` was called to migrate older entries.
+//!
+//! Indexed fields are encrypted like the others. Their index sets are keyed on a token of the value instead,
+//! an HMAC-SHA256 under the index key of the `KeyProvider` stored next to the field, which lets redis maintain
+//! the indexes without seeing the values. Tokens are deterministic, so objects sharing a value can be told apart
+//! from the others. The index key doesn't rotate, so that `find_by` keeps finding objects written under older keys.
+//! The version and schema version fields stay in clear, they only describe the entry.
+
+use std::collections::HashMap;
+#[cfg(feature = "encryption")]
+use std::convert::TryInto;
+#[cfg(feature = "encryption")]
+use std::sync::Arc;
+#[cfg(feature = "encryption")]
+use aes_gcm::{Aes256Gcm, Nonce, aead::{Aead, AeadCore, KeyInit, OsRng, Payload}};
+#[cfg(feature = "encryption")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "encryption")]
+use sha2::Sha256;
+#[cfg(feature = "encryption")]
+use crate::CacheError;
+#[cfg(feature = "encryption")]
+use crate::Result;
+
+pub(crate) const HEADER: u8 = 0xFE;
+#[cfg(feature = "encryption")]
+const AES_256_GCM: u8 = b'g';
+#[cfg(feature = "encryption")]
+const NONCE_LEN: usize = 12;
+
+/// A 256-bit AES key.
+pub type Key = [u8; 32];
+
+/// Source of the keys used to encrypt and decrypt values, such as a secret manager.
+pub trait KeyProvider: Send + Sync {
+    /// Id and value of the key new values are encrypted with.
+    fn current_key(&self) -> (u32, Key);
+    /// The key with the given id, `None` if it's unknown or retired.
+    fn key(&self, id: u32) -> Option<Key>;
+    /// The key index tokens are derived from. Unlike the current key it must never change,
+    /// or `find_by` would miss the objects indexed before.
+    fn index_key(&self) -> Key;
+}
+
+/// A fixed set of keys, the last one added being the current one.
+pub struct StaticKeys {
+    current: u32,
+    keys: HashMap<u32, Key>,
+    index: Key,
+}
+
+impl StaticKeys {
+    /// Starts from `key`, which the index key is derived from unless `with_index_key` is called.
+    pub fn new(id: u32, key: Key) -> Self {
+        let mut keys = HashMap::new();
+        keys.insert(id, key);
+        StaticKeys {
+            current: id,
+            keys,
+            index: key,
+        }
+    }
+
+    /// Sets the index key, needed to keep the same indexes once the first key is retired.
+    pub fn with_index_key(mut self, key: Key) -> Self {
+        self.index = key;
+        self
+    }
+
+    /// Makes `key` the current key, values encrypted with the previous ones can still be read.
+    pub fn rotate(mut self, id: u32, key: Key) -> Self {
+        self.keys.insert(id, key);
+        self.current = id;
+        self
+    }
+}
+
+impl KeyProvider for StaticKeys {
+    fn current_key(&self) -> (u32, Key) {
+        (self.current, self.keys[&self.current])
+    }
+
+    fn key(&self, id: u32) -> Option<Key> {
+        self.keys.get(&id).cloned()
+    }
+
+    fn index_key(&self) -> Key {
+        self.index
+    }
+}
+
+#[cfg(feature = "encryption")]
+#[derive(Clone)]
+pub(crate) struct Encryption {
+    keys: Arc<dyn KeyProvider>,
+}
+
+#[cfg(feature = "encryption")]
+impl Encryption {
+    pub fn new<P: KeyProvider + 'static>(keys: P) -> Self {
+        Encryption {
+            keys: Arc::new(keys),
+        }
+    }
+
+    /// Encrypts `value`, authenticating `aad` along with it.
+    pub fn seal(&self, value: &[u8], aad: &[u8]) -> Vec<u8> {
+        let (id, key) = self.keys.current_key();
+        let cipher = Aes256Gcm::new(&key.into());
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = cipher.encrypt(&nonce, Payload { msg: value, aad }).expect("AES-GCM encryption failed");
+
+        let mut out = Vec::with_capacity(6 + NONCE_LEN + sealed.len());
+        out.push(HEADER);
+        out.push(AES_256_GCM);
+        out.extend_from_slice(&id.to_be_bytes());
+        out.extend_from_slice(&nonce);
+        out.extend(sealed);
+        out
+    }
+
+    /// Deterministic token of `value` under the index key. The HMAC key is derived from it, so a provider
+    /// also using it for AES never uses the same key for both algorithms.
+    pub fn token(&self, value: &[u8]) -> String {
+        let mut mac = hmac(&hmac(&self.keys.index_key(), b"mouscache index token"), value);
+        mac.truncate(16);
+        mac.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Decrypts a value written by `seal` with the same `aad`.
+    pub fn open(&self, raw: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+        if raw.len() < 6 + NONCE_LEN || raw[0] != HEADER || raw[1] != AES_256_GCM {
+            return Err(CacheError::DecryptionError("Unknown encrypted value format".to_string()));
+        }
+
+        let id = u32::from_be_bytes(raw[2..6].try_into().unwrap());
+        let key = match self.keys.key(id) {
+            Some(key) => key,
+            None => return Err(CacheError::DecryptionError(format!("Unknown key id {}", id))),
+        };
+
+        let (nonce, sealed) = raw[6..].split_at(NONCE_LEN);
+        Aes256Gcm::new(&key.into())
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad })
+            .map_err(|_| CacheError::DecryptionError(format!("Value encrypted with key {} failed authentication", id)))
+    }
+}
+
+#[cfg(feature = "encryption")]
+fn hmac(key: &[u8], value: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(value);
+    mac.finalize().into_bytes().to_vec()
+}
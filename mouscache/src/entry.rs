@@ -0,0 +1,148 @@
+use std::marker::PhantomData;
+use crate::Result;
+use crate::Cacheable;
+use crate::memory_cache::MemoryEntryHandle;
+use crate::redis_cache::RedisEntryHandle;
+
+pub(crate) enum EntryHandle<'a> {
+    Memory(MemoryEntryHandle<'a>),
+    // Boxed as it holds a pooled connection, much larger than the memory handle
+    Redis(Box<RedisEntryHandle>),
+}
+
+impl<'a> EntryHandle<'a> {
+    fn store<O: Cacheable + Clone + 'static>(&mut self, obj: O, keep_ttl: bool) -> Result<()> {
+        match *self {
+            EntryHandle::Memory(ref mut h) => h.store(obj, keep_ttl),
+            EntryHandle::Redis(ref mut h) => h.store(obj, keep_ttl),
+        }
+    }
+
+    fn delete(&mut self) -> Result<()> {
+        match *self {
+            EntryHandle::Memory(ref mut h) => h.delete(),
+            EntryHandle::Redis(ref mut h) => h.delete(),
+        }
+    }
+}
+
+/// A view into a single cached object, obtained with `Cache::entry`.
+///
+/// The entry is locked (memory) or watched (redis) until it is consumed or dropped, so the
+/// composed operation is atomic. On redis a concurrent write makes the final write fail with
+/// `CacheError::ConflictError`. The same cache must not be accessed while an entry is held.
+pub enum Entry<'a, O: Cacheable + Clone + 'static> {
+    Occupied(OccupiedEntry<'a, O>),
+    Vacant(VacantEntry<'a, O>),
+}
+
+impl<'a, O: Cacheable + Clone + 'static> Entry<'a, O> {
+    pub fn key(&self) -> &str {
+        match *self {
+            Entry::Occupied(ref e) => e.key(),
+            Entry::Vacant(ref e) => e.key(),
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant, otherwise writes back pending modifications.
+    pub fn or_insert(self, default: O) -> Result<O> {
+        match self {
+            Entry::Occupied(e) => e.save(),
+            Entry::Vacant(e) => e.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> O>(self, f: F) -> Result<O> {
+        match self {
+            Entry::Occupied(e) => e.save(),
+            Entry::Vacant(e) => e.insert(f()),
+        }
+    }
+
+    /// Modifies an occupied entry in place. The change is written by `or_insert`,
+    /// `or_insert_with` or `OccupiedEntry::save`.
+    pub fn and_modify<F: FnOnce(&mut O)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut e) => {
+                f(e.get_mut());
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+}
+
+pub struct OccupiedEntry<'a, O: Cacheable + Clone + 'static> {
+    handle: EntryHandle<'a>,
+    key: String,
+    obj: O,
+    modified: bool,
+}
+
+impl<'a, O: Cacheable + Clone + 'static> OccupiedEntry<'a, O> {
+    pub(crate) fn new(handle: EntryHandle<'a>, key: String, obj: O) -> Self {
+        OccupiedEntry {
+            handle,
+            key,
+            obj,
+            modified: false,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn get(&self) -> &O {
+        &self.obj
+    }
+
+    pub fn get_mut(&mut self) -> &mut O {
+        self.modified = true;
+        &mut self.obj
+    }
+
+    /// Writes back the object if it was modified, keeping its remaining time to live.
+    pub fn save(mut self) -> Result<O> {
+        if self.modified {
+            self.handle.store(self.obj.clone(), true)?;
+        }
+        Ok(self.obj)
+    }
+
+    /// Replaces the object and returns the previous one.
+    pub fn insert(mut self, obj: O) -> Result<O> {
+        self.handle.store(obj, false)?;
+        Ok(self.obj)
+    }
+
+    pub fn remove(mut self) -> Result<O> {
+        self.handle.delete()?;
+        Ok(self.obj)
+    }
+}
+
+pub struct VacantEntry<'a, O: Cacheable + Clone + 'static> {
+    handle: EntryHandle<'a>,
+    key: String,
+    _marker: PhantomData<O>,
+}
+
+impl<'a, O: Cacheable + Clone + 'static> VacantEntry<'a, O> {
+    pub(crate) fn new(handle: EntryHandle<'a>, key: String) -> Self {
+        VacantEntry {
+            handle,
+            key,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn insert(mut self, obj: O) -> Result<O> {
+        self.handle.store(obj.clone(), false)?;
+        Ok(obj)
+    }
+}
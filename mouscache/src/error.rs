@@ -9,6 +9,10 @@ pub enum CacheError {
     DeletionError(String),
     AccessError(String),
     ConnectionError(String),
+    ConflictError(String),
+    DecryptionError(String),
+    IntegrityError(String),
+    ParseError(String),
     Other(String),
 }
 
@@ -22,6 +26,10 @@ impl std::fmt::Display for CacheError {
             DeletionError(ref desc) => write!(f, "Deletion error: {}", desc),
             AccessError(ref desc) => write!(f, "Access error: {}", desc),
             ConnectionError(ref desc) => write!(f, "Connection error: {}", desc),
+            ConflictError(ref desc) => write!(f, "Conflict error: {}", desc),
+            DecryptionError(ref desc) => write!(f, "Decryption error: {}", desc),
+            IntegrityError(ref desc) => write!(f, "Integrity error: {}", desc),
+            ParseError(ref desc) => write!(f, "Parse error: {}", desc),
             Other(ref desc) => write!(f, "Unknown error: {}", desc),
         }
     }
@@ -7,56 +7,237 @@ pub use redis::Value;
 pub use redis::FromRedisValue as FromValue;
 pub use redis::ToRedisArgs as ToArgs;
 
+pub mod codec;
+pub mod compression;
+pub mod encryption;
+#[cfg(feature = "serde")]
+pub mod serde_codec;
+mod entry;
 mod error;
 mod memory_cache;
 mod redis_cache;
 
-use std::{any::Any, collections::HashMap};
+use std::{any::{Any, TypeId}, collections::HashMap, sync::RwLock};
 
 use crate::memory_cache::MemoryCache;
 use crate::redis_cache::RedisCache;
+use crate::compression::Compression;
 pub use crate::error::CacheError;
+pub use crate::entry::{Entry, OccupiedEntry, VacantEntry};
 
 pub type Result<T> = std::result::Result<T, CacheError>;
 
+/// Reserved hash field holding the version mouscache maintains for every stored object.
+pub const VERSION_FIELD: &str = "__version";
+
+/// Reserved hash field holding a random id the redis cache gives every new hash, so that the versions of an object
+/// deleted and then written again never match those it had before.
+pub const EPOCH_FIELD: &str = "__epoch";
+
+/// Reserved hash field holding the variant name of enums using `#[derive(Cacheable)]`.
+pub const VARIANT_FIELD: &str = "__variant";
+
+/// Marker hash field stored for unit structs using `#[derive(Cacheable)]`.
+pub const UNIT_FIELD: &str = "__unit";
+
+/// Reserved hash field holding the schema version of objects written by the redis cache, see `Cacheable::schema_version`.
+pub const SCHEMA_FIELD: &str = "__schema";
+
+/// Prefix of the reserved hash fields holding the index tokens of indexed fields, written by redis caches
+/// with encryption instead of indexing the values themselves, see `mouscache::encryption`.
+pub const TOKEN_FIELD_PREFIX: &str = "__token:";
+
+/// `model_name` of the generic type `O` using `#[derive(Cacheable)]`, such as `Envelope<my_app::User>`: its name
+/// followed by the full `std::any::type_name` of its type arguments, so that instantiations never share keys.
+/// Each name is built and leaked once per type, later calls only look it up.
+#[doc(hidden)]
+pub fn generic_model_name<O: 'static>(name: &str, type_args: &[&str]) -> &'static str {
+    static NAMES: RwLock<Option<HashMap<TypeId, &'static str>>> = RwLock::new(None);
+
+    let id = TypeId::of::<O>();
+    if let Some(name) = NAMES.read().unwrap_or_else(|e| e.into_inner()).as_ref().and_then(|names| names.get(&id)) {
+        return name;
+    }
+
+    let mut names = NAMES.write().unwrap_or_else(|e| e.into_inner());
+    names.get_or_insert_with(HashMap::new).entry(id)
+        .or_insert_with(|| Box::leak(format!("{}<{}>", name, type_args.join(",")).into_boxed_str()))
+}
+
+/// How the redis cache stores objects of a `Cacheable` type, set with `#[cache(storage = "blob")]`.
+/// The memory cache keeps objects as is either way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Storage {
+    /// A hash of the fields produced by `to_redis_obj`, supporting every operation.
+    Hash,
+    /// A single string holding the fields encoded with `codec::encode_map`, written with SET/SETEX.
+    /// Versions, single field access and indexes aren't available, in either cache.
+    Blob,
+}
+
+/// Key of the set holding the keys of every `model` object whose indexed `field` is `value`.
+/// Its parts are separated by NUL bytes so that index sets never share a key with objects (`model:key`),
+/// and the field and value are escaped so that they can't contain the separator.
+fn index_key(model: &str, field: &str, value: &str) -> String {
+    let escape = |part: &str| part.replace('\\', "\\\\").replace('\0', "\\0");
+    format!("{}\0idx\0{}\0{}", model, escape(field), escape(value))
+}
+
+/// Blobs are read and written whole, by both caches so that they behave the same
+fn ensure_hash_storage<O: Cacheable>(operation: &str) -> Result<()> {
+    match O::storage() {
+        Storage::Hash => Ok(()),
+        Storage::Blob => Err(CacheError::Other(format!("{} uses blob storage, which doesn't support {}", O::model_name(), operation))),
+    }
+}
+
+fn ensure_indexed<O: Cacheable>(field: &str) -> Result<()> {
+    if O::indexed_fields().contains(&field) {
+        Ok(())
+    } else {
+        Err(CacheError::Other(format!("{} is not an indexed field of {}", field, O::model_name())))
+    }
+}
+
 pub trait Cacheable {
     fn model_name() -> &'static str where Self: Sized;
     fn to_redis_obj(&self) -> Vec<(String, String)>;
+    /// Fields of `to_redis_obj`, failing instead if one of them can't be encoded (see `codec::Codec`).
+    /// The caches write objects through it, so such objects are reported as errors, while the derived
+    /// `to_redis_obj` panics on them.
+    fn try_to_redis_obj(&self) -> Result<Vec<(String, String)>> {
+        Ok(self.to_redis_obj())
+    }
     fn from_redis_obj(obj: HashMap<String, String>) -> Result<Self> where Self: Sized;
+    /// Time to live in sec, `None` for entries which never expire. `Some(0)` expires right away:
+    /// writing such an object deletes the entry.
     fn expires_after(&self) -> Option<usize>;
     fn as_any(&self) -> &dyn Any;
+
+    /// Version of the field layout produced by `to_redis_obj`, set with `#[cache(version = N)]`.
+    ///
+    /// Entries stored under another version are passed through `migrate`, or treated as missing.
+    fn schema_version() -> u32 where Self: Sized {
+        0
+    }
+
+    /// Upgrades the fields of an entry written under the older schema version `from`,
+    /// `None` treats the entry as missing.
+    fn migrate(_from: u32, _obj: HashMap<String, String>) -> Option<HashMap<String, String>> where Self: Sized {
+        None
+    }
+
+    /// Fields of `to_redis_obj` the cache keeps an index of, set with `#[cache(index)]` and queried with `Cache::find_by`.
+    fn indexed_fields() -> &'static [&'static str] where Self: Sized {
+        &[]
+    }
+
+    /// Layout of the objects in the redis cache.
+    fn storage() -> Storage where Self: Sized {
+        Storage::Hash
+    }
+}
+
+/// Objects which know the key they are stored under, generated by `#[cache(key)]` on a field.
+pub trait CacheKey {
+    fn cache_key(&self) -> String;
+}
+
+/// Outcome of a `Cache::update` call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdateStatus {
+    /// Whether an object was stored under the key (the closure is not called otherwise).
+    pub existed: bool,
+    /// How many times the update was retried because of a concurrent write.
+    pub retries: usize,
 }
 
 use std::str::FromStr;
 
+/// Parses a hash value, or a set member when `field` is `None`, read from `key`.
+pub(crate) fn parse_value<T: FromStr>(key: &str, field: Option<&str>, raw: &str) -> Result<T> {
+    T::from_str(raw).map_err(|_| match field {
+        Some(field) => CacheError::ParseError(format!("Unable to parse field {} of {} from {:?}", field, key, raw)),
+        None => CacheError::ParseError(format!("Unable to parse a member of {} from {:?}", key, raw)),
+    })
+}
+
+/// Redis-like hash and set operations on raw keys.
+///
+/// Values are binary-safe: the `_bytes` variants read and write them as is, while the other functions
+/// fail to read values which aren't valid UTF-8. The `_as` variants and `hash_get_map` parse values with `FromStr`,
+/// failing with a `CacheError::ParseError` naming the key, field and value which couldn't be parsed.
 pub trait CacheFunc {
     // Redis-like HashSet related functions
     fn hash_delete(&self, key: &str, fields: &[&str]) -> Result<bool>;
     fn hash_exists(&self, key: &str, field: &str) -> Result<bool>;
     fn hash_get<T: FromStr>(&self, key: &str, field: &str) -> Result<Option<T>>;
+    fn hash_get_bytes(&self, key: &str, field: &str) -> Result<Option<Vec<u8>>>;
     fn hash_get_all<T: Cacheable + Clone + 'static>(&self, key: &str) -> Result<Option<T>>;
+    fn hash_get_map<T: FromStr>(&self, key: &str) -> Result<HashMap<String, T>>;
     fn hash_keys(&self, key: &str) -> Result<Vec<String>>;
     fn hash_len(&self, key: &str) -> Result<usize>;
     fn hash_multiple_get(&self, key: &str, fields: &[&str]) -> Result<Vec<Option<String>>>;
     fn hash_multiple_set<V: ToString>(&self, key: &str, fv_pairs: &[(&str, V)] ) -> Result<bool>;
     fn hash_set<V: ToString>(&self, key: &str, field: &str, value: V) -> Result<bool>;
+    fn hash_set_bytes<V: AsRef<[u8]>>(&self, key: &str, field: &str, value: V) -> Result<bool>;
     fn hash_set_all<T: Cacheable + Clone + 'static>(&self, key: &str, cacheable: T) -> Result<bool>;
     fn hash_set_if_not_exists<V: ToString>(&self, key: &str, field: &str, value: V) -> Result<bool>;
     fn hash_values(&self, key: &str) -> Result<Vec<String>>;
+    fn hash_values_bytes(&self, key: &str) -> Result<Vec<Vec<u8>>>;
     // Redis-like Set related functions
     fn set_add<V: ToString>(&self, key: &str, members: &[V]) -> Result<bool>;
+    fn set_add_bytes<V: AsRef<[u8]>>(&self, key: &str, members: &[V]) -> Result<bool>;
     fn set_card(&self, key: &str) -> Result<u64>;
     fn set_diff(&self, keys: &[&str]) -> Result<Vec<String>>;
     fn set_diffstore(&self, diff_name: &str, keys: &[&str]) -> Result<u64>;
     fn set_inter(&self, keys: &[&str]) -> Result<Vec<String>>;
     fn set_interstore(&self, inter_name: &str, keys: &[&str]) -> Result<u64>;
     fn set_ismember<V: ToString>(&self, key: &str, member: V) -> Result<bool>;
+    fn set_ismember_bytes<V: AsRef<[u8]>>(&self, key: &str, member: V) -> Result<bool>;
     fn set_members(&self, key: &str) -> Result<Vec<String>>;
+    fn set_members_bytes(&self, key: &str) -> Result<Vec<Vec<u8>>>;
     fn set_move<V: ToString>(&self, key1: &str, key2: &str, member: V) -> Result<bool>;
     fn set_rem<V: ToString>(&self, key: &str, member: V) -> Result<bool>;
+    fn set_rem_bytes<V: AsRef<[u8]>>(&self, key: &str, member: V) -> Result<bool>;
     fn set_union(&self, keys: &[&str]) -> Result<Vec<String>>;
     fn set_unionstore(&self, union_name: &str, keys: &[&str]) -> Result<u64>;
 
+    fn hash_multiple_get_as<T: FromStr>(&self, key: &str, fields: &[&str]) -> Result<Vec<Option<T>>> {
+        let values = self.hash_multiple_get(key, fields)?;
+        fields.iter().zip(values).map(|(field, value)| {
+            value.map(|value| parse_value(key, Some(field), &value)).transpose()
+        }).collect()
+    }
+
+    fn hash_values_as<T: FromStr>(&self, key: &str) -> Result<Vec<T>> {
+        // Read along with the field names, so a parse error can tell which field holds the value
+        Ok(self.hash_get_map(key)?.into_values().collect())
+    }
+
+    fn set_diff_as<T: FromStr>(&self, keys: &[&str]) -> Result<Vec<T>> {
+        // Members of the difference all come from the first set
+        let source = keys.first().cloned().unwrap_or_default();
+        self.set_diff(keys)?.iter().map(|member| parse_value(source, None, member)).collect()
+    }
+
+    fn set_inter_as<T: FromStr>(&self, keys: &[&str]) -> Result<Vec<T>> {
+        let sources = keys.join(", ");
+        self.set_inter(keys)?.iter().map(|member| parse_value(&sources, None, member)).collect()
+    }
+
+    fn set_members_as<T: FromStr>(&self, key: &str) -> Result<Vec<T>> {
+        self.set_members(key)?.iter().map(|member| parse_value(key, None, member)).collect()
+    }
+
+    fn set_union_as<T: FromStr>(&self, keys: &[&str]) -> Result<Vec<T>> {
+        self.set_union(keys)?.iter().map(|member| {
+            T::from_str(member).or_else(|_| {
+                let sources = keys.iter().filter(|key| self.set_ismember(key, member).unwrap_or(false)).cloned().collect::<Vec<_>>();
+                parse_value(&sources.join(", "), None, member)
+            })
+        }).collect()
+    }
 }
 
 trait CacheAccess {
@@ -65,6 +246,16 @@ trait CacheAccess {
     fn get<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Option<O>>;
     fn contains_key<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<bool>;
     fn remove<K: ToString, O: Cacheable>(&self, key: K) -> Result<()>;
+    fn take<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Option<O>>;
+    fn swap<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K, obj: O) -> Result<Option<O>>;
+    fn update<K: ToString, O: Cacheable + Clone + 'static, F: FnMut(&mut O)>(&self, key: K, f: F) -> Result<UpdateStatus>;
+    fn entry<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Entry<'_, O>>;
+    fn get_versioned<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Option<(O, u64)>>;
+    fn insert_if_version<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K, obj: O, version: u64) -> Result<u64>;
+    fn get_field<K: ToString, O: Cacheable + 'static>(&self, key: K, field: &str) -> Result<Option<String>>;
+    fn set_field<K: ToString, O: Cacheable + 'static, V: ToString>(&self, key: K, field: &str, value: V) -> Result<bool>;
+    fn delete_field<K: ToString, O: Cacheable + 'static>(&self, key: K, field: &str) -> Result<bool>;
+    fn find_by<O: Cacheable + Clone + 'static, V: ToString>(&self, field: &str, value: V) -> Result<Vec<O>>;
 }
 
 pub enum Cache {
@@ -84,6 +275,46 @@ impl Clone for Cache {
 use crate::Cache::*;
 
 impl Cache {
+    /// Compresses the large objects written by this cache and the clones made from it, see `compression`.
+    /// Objects already stored are still read as is.
+    pub fn with_compression(self, compression: Compression) -> Cache {
+        match self {
+            Memory(c) => Memory(c.with_compression(compression)),
+            Redis(c) => Redis(c.with_compression(compression)),
+        }
+    }
+
+    /// Splits the blobs (see `Storage::Blob`) this cache and the clones made from it write to redis in chunks of
+    /// at most `chunk_size` bytes, stored next to the blob key. The memory cache is left as is.
+    pub fn with_chunking(self, chunk_size: usize) -> Cache {
+        match self {
+            Memory(c) => Memory(c),
+            Redis(c) => Redis(c.with_chunking(chunk_size)),
+        }
+    }
+
+    /// Encrypts the objects this cache and the clones made from it write to redis with keys from `keys`,
+    /// see `encryption`. The values written by the raw hash and set functions of `CacheFunc` are still stored
+    /// in clear. The memory cache keeps objects in process, so it's left as is.
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption<P: encryption::KeyProvider + 'static>(self, keys: P) -> Cache {
+        match self {
+            Memory(c) => Memory(c),
+            Redis(c) => Redis(c.with_encryption(keys)),
+        }
+    }
+
+    /// Lets an encrypting cache read the values written in clear, before encryption was turned on, instead of
+    /// failing with `CacheError::DecryptionError`. Meant for migrating existing entries: anyone able to write
+    /// to redis could otherwise slip in values the cache would trust.
+    #[cfg(feature = "encryption")]
+    pub fn allow_plaintext_reads(self) -> Cache {
+        match self {
+            Memory(c) => Memory(c),
+            Redis(c) => Redis(c.allow_plaintext_reads()),
+        }
+    }
+
     pub fn insert<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K, obj: O) -> Result<()> {
         match *self {
             Memory(ref c) => c.insert(key, obj),
@@ -111,6 +342,107 @@ impl Cache {
             Redis(ref c) => c.remove::<K, O>(key),
         }
     }
+
+    /// Inserts `obj` under its own key.
+    pub fn put<O: Cacheable + CacheKey + Clone + 'static>(&self, obj: O) -> Result<()> {
+        let key = obj.cache_key();
+        self.insert(key, obj)
+    }
+
+    /// Removes the object stored under the key of `obj`.
+    pub fn delete_obj<O: Cacheable + CacheKey>(&self, obj: &O) -> Result<()> {
+        self.remove::<String, O>(obj.cache_key())
+    }
+
+    /// Reloads the cached copy of `obj`, `None` if it was removed or expired.
+    pub fn refresh<O: Cacheable + CacheKey + Clone + 'static>(&self, obj: &O) -> Result<Option<O>> {
+        self.get::<String, O>(obj.cache_key())
+    }
+
+    /// Atomically removes the object stored under `key` and returns it.
+    pub fn take<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Option<O>> {
+        match *self {
+            Memory(ref c) => c.take::<K, O>(key),
+            Redis(ref c) => c.take::<K, O>(key),
+        }
+    }
+
+    /// Atomically replaces the object stored under `key` and returns the previous one.
+    pub fn swap<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K, obj: O) -> Result<Option<O>> {
+        match *self {
+            Memory(ref c) => c.swap(key, obj),
+            Redis(ref c) => c.swap(key, obj),
+        }
+    }
+
+    /// Atomically applies `f` to the object stored under `key`, retrying on concurrent writes.
+    /// The entry keeps its remaining time to live.
+    pub fn update<K: ToString, O: Cacheable + Clone + 'static, F: FnMut(&mut O)>(&self, key: K, f: F) -> Result<UpdateStatus> {
+        match *self {
+            Memory(ref c) => c.update::<K, O, F>(key, f),
+            Redis(ref c) => c.update::<K, O, F>(key, f),
+        }
+    }
+
+    /// Gets the entry for the object stored under `key` for in-place manipulation.
+    pub fn entry<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Entry<'_, O>> {
+        match *self {
+            Memory(ref c) => c.entry::<K, O>(key),
+            Redis(ref c) => c.entry::<K, O>(key),
+        }
+    }
+
+    /// Gets the object stored under `key` along with its current version. Versions change with every write and are
+    /// never reused for a key, even after its object was removed or expired, but they don't follow each other.
+    pub fn get_versioned<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Option<(O, u64)>> {
+        match *self {
+            Memory(ref c) => c.get_versioned::<K, O>(key),
+            Redis(ref c) => c.get_versioned::<K, O>(key),
+        }
+    }
+
+    /// Inserts `obj` only if the stored object is still at `version` (0 meaning no object),
+    /// returning the new version. Fails with `CacheError::ConflictError` otherwise.
+    pub fn insert_if_version<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K, obj: O, version: u64) -> Result<u64> {
+        match *self {
+            Memory(ref c) => c.insert_if_version(key, obj, version),
+            Redis(ref c) => c.insert_if_version(key, obj, version),
+        }
+    }
+
+    /// Reads a single field of the object stored under `key`, as produced by `to_redis_obj`.
+    pub fn get_field<K: ToString, O: Cacheable + 'static>(&self, key: K, field: &str) -> Result<Option<String>> {
+        match *self {
+            Memory(ref c) => c.get_field::<K, O>(key, field),
+            Redis(ref c) => c.get_field::<K, O>(key, field),
+        }
+    }
+
+    /// Writes a single field of the object stored under `key`, leaving the others untouched.
+    /// Returns false if there is no such object.
+    pub fn set_field<K: ToString, O: Cacheable + 'static, V: ToString>(&self, key: K, field: &str, value: V) -> Result<bool> {
+        match *self {
+            Memory(ref c) => c.set_field::<K, O, V>(key, field, value),
+            Redis(ref c) => c.set_field::<K, O, V>(key, field, value),
+        }
+    }
+
+    /// Removes a single field of the object stored under `key`. Returns false if there is no such object.
+    pub fn delete_field<K: ToString, O: Cacheable + 'static>(&self, key: K, field: &str) -> Result<bool> {
+        match *self {
+            Memory(ref c) => c.delete_field::<K, O>(key, field),
+            Redis(ref c) => c.delete_field::<K, O>(key, field),
+        }
+    }
+
+    /// Gets every object whose indexed `field` (see `#[cache(index)]`) is `value`, in no particular order.
+    /// Fails if `field` isn't indexed.
+    pub fn find_by<O: Cacheable + Clone + 'static, V: ToString>(&self, field: &str, value: V) -> Result<Vec<O>> {
+        match *self {
+            Memory(ref c) => c.find_by::<O, V>(field, value),
+            Redis(ref c) => c.find_by::<O, V>(field, value),
+        }
+    }
 }
 
 impl CacheFunc for Cache {
@@ -135,6 +467,13 @@ impl CacheFunc for Cache {
         }
     }
 
+    fn hash_get_bytes(&self, key: &str, field: &str) -> Result<Option<Vec<u8>>> {
+        match *self {
+            Memory(ref m) => m.hash_get_bytes(key, field),
+            Redis(ref r) => r.hash_get_bytes(key, field),
+        }
+    }
+
     fn hash_get_all<T: Cacheable + Clone + 'static>(&self, key: &str) -> Result<Option<T>> {
         match *self {
             Memory(ref m) => m.hash_get_all(key),
@@ -142,6 +481,13 @@ impl CacheFunc for Cache {
         }
     }
 
+    fn hash_get_map<T: FromStr>(&self, key: &str) -> Result<HashMap<String, T>> {
+        match *self {
+            Memory(ref m) => m.hash_get_map(key),
+            Redis(ref r) => r.hash_get_map(key),
+        }
+    }
+
     fn hash_keys(&self, key: &str) -> Result<Vec<String>> {
         match *self {
             Memory(ref m) => m.hash_keys(key),
@@ -177,6 +523,13 @@ impl CacheFunc for Cache {
         }
     }
 
+    fn hash_set_bytes<V: AsRef<[u8]>>(&self, key: &str, field: &str, value: V) -> Result<bool> {
+        match *self {
+            Memory(ref m) => m.hash_set_bytes(key, field, value),
+            Redis(ref r) => r.hash_set_bytes(key, field, value),
+        }
+    }
+
     fn hash_set_all<T: Cacheable + Clone + 'static>(&self, key: &str, cacheable: T) -> Result<bool> {
         match *self {
             Memory(ref m) => m.hash_set_all(key, cacheable),
@@ -198,6 +551,13 @@ impl CacheFunc for Cache {
         }
     }
 
+    fn hash_values_bytes(&self, key: &str) -> Result<Vec<Vec<u8>>> {
+        match *self {
+            Memory(ref m) => m.hash_values_bytes(key),
+            Redis(ref r) => r.hash_values_bytes(key),
+        }
+    }
+
     fn set_add<V: ToString>(&self, key: &str, members: &[V]) -> Result<bool> {
         match *self {
             Memory(ref m) => m.set_add(key, members),
@@ -205,6 +565,13 @@ impl CacheFunc for Cache {
         }
     }
 
+    fn set_add_bytes<V: AsRef<[u8]>>(&self, key: &str, members: &[V]) -> Result<bool> {
+        match *self {
+            Memory(ref m) => m.set_add_bytes(key, members),
+            Redis(ref r) => r.set_add_bytes(key, members),
+        }
+    }
+
     fn set_card(&self, key: &str) -> Result<u64> {
         match *self {
             Memory(ref m) => m.set_card(key),
@@ -247,6 +614,13 @@ impl CacheFunc for Cache {
         }
     }
 
+    fn set_ismember_bytes<V: AsRef<[u8]>>(&self, key: &str, member: V) -> Result<bool> {
+        match *self {
+            Memory(ref m) => m.set_ismember_bytes(key, member),
+            Redis(ref r) => r.set_ismember_bytes(key, member),
+        }
+    }
+
     fn set_members(&self, key: &str) -> Result<Vec<String>> {
         match *self {
             Memory(ref m) => m.set_members(key),
@@ -254,6 +628,13 @@ impl CacheFunc for Cache {
         }
     }
 
+    fn set_members_bytes(&self, key: &str) -> Result<Vec<Vec<u8>>> {
+        match *self {
+            Memory(ref m) => m.set_members_bytes(key),
+            Redis(ref r) => r.set_members_bytes(key),
+        }
+    }
+
     fn set_move<V: ToString>(&self, key1: &str, key2: &str, member: V) -> Result<bool> {
         match *self {
             Memory(ref m) => m.set_move(key1, key2, member),
@@ -268,6 +649,13 @@ impl CacheFunc for Cache {
         }
     }
 
+    fn set_rem_bytes<V: AsRef<[u8]>>(&self, key: &str, member: V) -> Result<bool> {
+        match *self {
+            Memory(ref m) => m.set_rem_bytes(key, member),
+            Redis(ref r) => r.set_rem_bytes(key, member),
+        }
+    }
+
     fn set_union(&self, keys: &[&str]) -> Result<Vec<String>> {
         match *self {
             Memory(ref m) => m.set_union(keys),
@@ -1,14 +1,23 @@
+use std::any::Any;
 use std::time::{Instant, Duration};
 use std::collections::hash_map::HashMap;
 use std::collections::hash_set::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
 use crate::Result;
+use crate::CacheError;
 use crate::Cacheable;
+use crate::codec;
+use crate::compression::{self, Compression};
 use crate::CacheAccess;
 use crate::CacheFunc;
-use parking_lot::RwLock;
+use crate::UpdateStatus;
+use crate::entry::{Entry, EntryHandle, OccupiedEntry, VacantEntry};
+use parking_lot::{RwLock, RwLockWriteGuard};
 use std::sync::Arc;
 use std::str::FromStr;
 
+const MAX_UPDATE_RETRIES: usize = 100;
+
 struct Expiration {
     insertion_time: Instant,
     ttl: Duration,
@@ -28,12 +37,118 @@ impl Expiration {
     }
 }
 
-type MemCacheable = (Box<dyn Cacheable>, Option<Expiration>);
+type MemCacheable = (Box<dyn Cacheable>, Option<Expiration>, u64);
+
+/// A hash written with the `CacheFunc` functions, field values being kept as bytes
+type MemHash = RwLock<HashMap<String, Vec<u8>>>;
+
+fn cached(entry: &MemCacheable) -> &dyn Cacheable {
+    &*entry.0
+}
+
+/// Fields of an object stored compressed, decompressed whenever they're read
+struct CompressedObject(Vec<u8>);
+
+impl CompressedObject {
+    fn fields(&self) -> Result<Vec<(String, String)>> {
+        compression::decompress(self.0.clone()).and_then(|raw| codec::decode_map(&raw))
+    }
+}
+
+impl Cacheable for CompressedObject {
+    fn model_name() -> &'static str {
+        "__compressed"
+    }
+
+    // Empty if the object can't be decompressed, `stored_fields` reports why
+    fn to_redis_obj(&self) -> Vec<(String, String)> {
+        self.fields().unwrap_or_default()
+    }
+
+    fn from_redis_obj(_obj: HashMap<String, String>) -> Result<Self> {
+        Err(CacheError::Other("Compressed objects are only built by the memory cache".to_string()))
+    }
+
+    fn expires_after(&self) -> Option<usize> {
+        None
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// `obj` as kept in the cache, compressed if its encoded fields reach the threshold. Objects are only
+/// encoded when they're compressed or indexed, failing if one of their fields can't be.
+fn boxed<O: Cacheable + 'static>(obj: O, compression: Option<&Compression>) -> Result<Box<dyn Cacheable>> {
+    if let Some(compression) = compression {
+        let encoded = codec::encode_object(&obj)?;
+        if encoded.len() >= compression.threshold {
+            return Ok(Box::new(CompressedObject(compression.compress(encoded))));
+        }
+    } else if !O::indexed_fields().is_empty() {
+        obj.try_to_redis_obj()?;
+    }
+    Ok(Box::new(obj))
+}
+
+/// The `O` kept in the cache as `obj`
+fn unboxed<O: Cacheable + Clone + 'static>(obj: &dyn Cacheable) -> Result<O> {
+    if obj.as_any().is::<CompressedObject>() {
+        return O::from_redis_obj(stored_fields(obj)?.into_iter().collect());
+    }
+
+    match obj.as_any().downcast_ref::<O>() {
+        Some(struct_obj) => Ok(struct_obj.clone()),
+        None => Err(CacheError::AccessError(format!("Cached {} isn't a {}", O::model_name(), std::any::type_name::<O>()))),
+    }
+}
+
+/// The fields of `obj`, failing if it was compressed and can't be decompressed
+fn stored_fields(obj: &dyn Cacheable) -> Result<Vec<(String, String)>> {
+    match obj.as_any().downcast_ref::<CompressedObject>() {
+        Some(compressed) => compressed.fields(),
+        None => obj.try_to_redis_obj(),
+    }
+}
+
+/// Indexed fields of a model, see `Cacheable::indexed_fields`
+#[derive(Clone, Copy)]
+struct IndexSpec {
+    model: &'static str,
+    fields: &'static [&'static str],
+}
+
+impl IndexSpec {
+    fn of<O: Cacheable>() -> Self {
+        IndexSpec {
+            model: O::model_name(),
+            fields: O::indexed_fields(),
+        }
+    }
+
+    // Indexed objects were encoded once when they were stored (see `boxed`)
+    fn index_keys(&self, obj: &dyn Cacheable) -> Vec<String> {
+        if self.fields.is_empty() {
+            return Vec::new();
+        }
+
+        obj.try_to_redis_obj().unwrap_or_default().into_iter()
+            .filter(|(field, _)| self.fields.contains(&field.as_str()))
+            .map(|(field, value)| crate::index_key(self.model, &field, &value))
+            .collect()
+    }
+}
 
 struct Inner {
     pub obj_cache: RwLock<HashMap<String, MemCacheable>>,
-    pub hashsets: RwLock<HashMap<String, RwLock<HashMap<String, String>>>>,
-    pub sets: RwLock<HashMap<String, RwLock<HashSet<String>>>>,
+    pub hashsets: RwLock<HashMap<String, MemHash>>,
+    pub sets: RwLock<HashMap<String, RwLock<HashSet<Vec<u8>>>>>,
+    /// Keys of the objects stored under each index key, only written while holding `obj_cache`
+    pub indexes: RwLock<HashMap<String, HashSet<String>>>,
+    /// Last version given to an object. Versions are shared by every key, so that an object removed and then
+    /// written again never gets back a version it had before.
+    pub versions: AtomicU64,
 }
 
 impl Inner {
@@ -42,6 +157,33 @@ impl Inner {
             obj_cache: RwLock::new(HashMap::new()),
             hashsets: RwLock::new(HashMap::new()),
             sets: RwLock::new(HashMap::new()),
+            indexes: RwLock::new(HashMap::new()),
+            versions: AtomicU64::new(0),
+        }
+    }
+
+    /// Draws a version no entry has ever had, so a stale version can't match a reinserted key
+    fn next_version(&self) -> u64 {
+        self.versions.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Moves `tkey` from the indexes of `old` to the indexes of `new`
+    fn reindex(&self, spec: IndexSpec, tkey: &str, old: Option<&dyn Cacheable>, new: Option<&dyn Cacheable>) {
+        if spec.fields.is_empty() {
+            return;
+        }
+
+        let mut indexes = self.indexes.write();
+        for index in old.map(|obj| spec.index_keys(obj)).unwrap_or_default() {
+            if let Some(keys) = indexes.get_mut(&index) {
+                keys.remove(tkey);
+                if keys.is_empty() {
+                    indexes.remove(&index);
+                }
+            }
+        }
+        for index in new.map(|obj| spec.index_keys(obj)).unwrap_or_default() {
+            indexes.entry(index).or_default().insert(tkey.to_string());
         }
     }
 
@@ -79,13 +221,15 @@ impl Inner {
 }
 
 pub struct MemoryCache {
-    inner: Arc<Inner>
+    inner: Arc<Inner>,
+    compression: Option<Compression>,
 }
 
 impl Clone for MemoryCache {
     fn clone(&self) -> Self {
         MemoryCache {
             inner: self.inner.clone(),
+            compression: self.compression,
         }
     }
 }
@@ -93,9 +237,44 @@ impl Clone for MemoryCache {
 impl MemoryCache {
     pub fn new() -> MemoryCache {
         MemoryCache {
-            inner: Arc::new(Inner::new())
+            inner: Arc::new(Inner::new()),
+            compression: None,
         }
     }
+
+    pub fn with_compression(self, compression: Compression) -> Self {
+        MemoryCache {
+            compression: Some(compression),
+            ..self
+        }
+    }
+
+    fn write_field<K: ToString, O: Cacheable + 'static>(&self, key: K, field: &str, value: Option<String>) -> Result<bool> {
+        crate::ensure_hash_storage::<O>("single field access")?;
+
+        let tkey = gen_key::<K, O>(key);
+
+        let mut cache = self.inner.obj_cache.write();
+
+        if current_version(cache.get(&tkey)) == 0 {
+            return Ok(false);
+        }
+
+        if let Some(entry) = cache.get_mut(&tkey) {
+            let mut map: HashMap<String, String> = stored_fields(&*entry.0)?.into_iter().collect();
+            match value {
+                Some(value) => map.insert(field.to_string(), value),
+                None => map.remove(field),
+            };
+
+            let previous = std::mem::replace(&mut entry.0, boxed(O::from_redis_obj(map)?, self.compression.as_ref())?);
+            entry.2 = self.inner.next_version();
+            self.inner.reindex(IndexSpec::of::<O>(), &tkey, Some(&*previous), Some(&*entry.0));
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
 }
 
 impl CacheAccess for MemoryCache {
@@ -109,7 +288,8 @@ impl CacheAccess for MemoryCache {
 
         let exp = expires_after.map(|ttl| { Expiration::new(ttl) });
 
-        self.inner.obj_cache.write().insert(tkey, (Box::new(obj), exp));
+        let mut cache = self.inner.obj_cache.write();
+        replace_entry(&mut cache, &self.inner, IndexSpec::of::<O>(), &tkey, boxed(obj, self.compression.as_ref())?, exp);
         Ok(())
     }
 
@@ -120,7 +300,7 @@ impl CacheAccess for MemoryCache {
 
         {
             let cache = self.inner.obj_cache.read();
-            if let Some(&(ref obj, ref exp)) = cache.get(&tkey) {
+            if let Some(&(ref obj, ref exp, _)) = cache.get(&tkey) {
                 if let &Some(ref exp) = exp {
                     if exp.is_expired() {
                         delete_entry = true;
@@ -128,19 +308,15 @@ impl CacheAccess for MemoryCache {
                 }
 
                 if !delete_entry {
-                    let struct_obj: O = match obj.as_any().downcast_ref::<O>() {
-                        Some(struct_obj) => struct_obj.clone(),
-                        None => panic!("Invalid type in mouscache")
-                    };
-
-                    return Ok(Some(struct_obj));
+                    return unboxed::<O>(&**obj).map(Some);
                 }
             }
         }
 
         if delete_entry {
             let mut cache = self.inner.obj_cache.write();
-            cache.remove(&tkey);
+            let expired = cache.remove(&tkey);
+            self.inner.reindex(IndexSpec::of::<O>(), &tkey, expired.as_ref().map(cached), None);
         }
 
         Ok(None)
@@ -154,15 +330,257 @@ impl CacheAccess for MemoryCache {
 
     fn remove<K: ToString, O: Cacheable>(&self, key: K) -> Result<()> {
         let tkey = gen_key::<K, O>(key);
-        self.inner.obj_cache.write().remove(&tkey);
+        let mut cache = self.inner.obj_cache.write();
+        let previous = cache.remove(&tkey);
+        self.inner.reindex(IndexSpec::of::<O>(), &tkey, previous.as_ref().map(cached), None);
+        Ok(())
+    }
+
+    fn take<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Option<O>> {
+        let tkey = gen_key::<K, O>(key);
+
+        let mut cache = self.inner.obj_cache.write();
+        let entry = cache.remove(&tkey);
+        self.inner.reindex(IndexSpec::of::<O>(), &tkey, entry.as_ref().map(cached), None);
+
+        live_object::<O>(entry.as_ref())
+    }
+
+    fn swap<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K, obj: O) -> Result<Option<O>> {
+        let tkey = gen_key::<K, O>(key);
+
+        let exp = obj.expires_after().map(|ttl| { Expiration::new(ttl) });
+
+        let mut cache = self.inner.obj_cache.write();
+        let (previous, _) = replace_entry(&mut cache, &self.inner, IndexSpec::of::<O>(), &tkey, boxed(obj, self.compression.as_ref())?, exp);
+
+        live_object::<O>(previous.as_ref())
+    }
+
+    fn update<K: ToString, O: Cacheable + Clone + 'static, F: FnMut(&mut O)>(&self, key: K, mut f: F) -> Result<UpdateStatus> {
+        let tkey = gen_key::<K, O>(key);
+
+        for retries in 0..MAX_UPDATE_RETRIES {
+            // `f` runs without holding the lock, the update only lands if nothing was written in the meantime
+            let current = {
+                let cache = self.inner.obj_cache.read();
+                let entry = cache.get(&tkey);
+                (live_object::<O>(entry)?, entry.map(|entry| entry.2))
+            };
+
+            let (mut obj, version) = match current {
+                (Some(obj), Some(version)) => (obj, version),
+                (None, Some(version)) => {
+                    let mut cache = self.inner.obj_cache.write();
+                    if cache.get(&tkey).map(|entry| entry.2) == Some(version) {
+                        let expired = cache.remove(&tkey);
+                        self.inner.reindex(IndexSpec::of::<O>(), &tkey, expired.as_ref().map(cached), None);
+                    }
+                    return Ok(UpdateStatus { existed: false, retries });
+                }
+                _ => return Ok(UpdateStatus { existed: false, retries }),
+            };
+
+            f(&mut obj);
+
+            let mut cache = self.inner.obj_cache.write();
+            if current_version(cache.get(&tkey)) != version {
+                continue;
+            }
+
+            if let Some(entry) = cache.get_mut(&tkey) {
+                let previous = std::mem::replace(&mut entry.0, boxed(obj, self.compression.as_ref())?);
+                entry.2 = self.inner.next_version();
+                self.inner.reindex(IndexSpec::of::<O>(), &tkey, Some(&*previous), Some(&*entry.0));
+            }
+            return Ok(UpdateStatus { existed: true, retries });
+        }
+
+        Err(CacheError::AccessError(format!("Unable to update {} after {} retries", tkey, MAX_UPDATE_RETRIES)))
+    }
+
+    fn entry<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Entry<'_, O>> {
+        let key = key.to_string();
+        let tkey = gen_key::<&str, O>(&key);
+
+        let cache = self.inner.obj_cache.write();
+
+        let obj = live_object::<O>(cache.get(&tkey))?;
+
+        let handle = EntryHandle::Memory(MemoryEntryHandle {
+            cache,
+            tkey,
+            inner: &self.inner,
+            spec: IndexSpec::of::<O>(),
+            compression: self.compression,
+        });
+
+        match obj {
+            Some(obj) => Ok(Entry::Occupied(OccupiedEntry::new(handle, key, obj))),
+            None => Ok(Entry::Vacant(VacantEntry::new(handle, key))),
+        }
+    }
+
+    fn get_versioned<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Option<(O, u64)>> {
+        crate::ensure_hash_storage::<O>("versions")?;
+
+        let tkey = gen_key::<K, O>(key);
+
+        let cache = self.inner.obj_cache.read();
+
+        let entry = cache.get(&tkey);
+        Ok(live_object::<O>(entry)?.zip(entry.map(|entry| entry.2)))
+    }
+
+    fn insert_if_version<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K, obj: O, version: u64) -> Result<u64> {
+        crate::ensure_hash_storage::<O>("versions")?;
+
+        let tkey = gen_key::<K, O>(key);
+
+        let exp = obj.expires_after().map(|ttl| { Expiration::new(ttl) });
+
+        let mut cache = self.inner.obj_cache.write();
+
+        let current = current_version(cache.get(&tkey));
+        if current != version {
+            return Err(crate::CacheError::ConflictError(format!("{} is no longer at version {}", tkey, version)));
+        }
+
+        let (_, version) = replace_entry(&mut cache, &self.inner, IndexSpec::of::<O>(), &tkey, boxed(obj, self.compression.as_ref())?, exp);
+        Ok(version)
+    }
+
+    fn get_field<K: ToString, O: Cacheable + 'static>(&self, key: K, field: &str) -> Result<Option<String>> {
+        crate::ensure_hash_storage::<O>("single field access")?;
+
+        let tkey = gen_key::<K, O>(key);
+
+        let cache = self.inner.obj_cache.read();
+
+        if let Some(entry) = cache.get(&tkey) {
+            if current_version(Some(entry)) > 0 {
+                return Ok(stored_fields(&*entry.0)?.into_iter().find(|&(ref f, _)| f == field).map(|(_, v)| v));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn set_field<K: ToString, O: Cacheable + 'static, V: ToString>(&self, key: K, field: &str, value: V) -> Result<bool> {
+        self.write_field::<K, O>(key, field, Some(value.to_string()))
+    }
+
+    fn delete_field<K: ToString, O: Cacheable + 'static>(&self, key: K, field: &str) -> Result<bool> {
+        self.write_field::<K, O>(key, field, None)
+    }
+
+    fn find_by<O: Cacheable + Clone + 'static, V: ToString>(&self, field: &str, value: V) -> Result<Vec<O>> {
+        crate::ensure_hash_storage::<O>("indexes")?;
+        crate::ensure_indexed::<O>(field)?;
+
+        let spec = IndexSpec::of::<O>();
+        let index = crate::index_key(spec.model, field, &value.to_string());
+
+        let mut cache = self.inner.obj_cache.write();
+
+        let tkeys: Vec<String> = match self.inner.indexes.read().get(&index) {
+            Some(keys) => keys.iter().cloned().collect(),
+            None => return Ok(Vec::new()),
+        };
+
+        let mut found = Vec::new();
+        for tkey in tkeys {
+            match live_object::<O>(cache.get(&tkey))? {
+                Some(obj) => found.push(obj),
+                None => {
+                    // Drop expired entries along with their indexes
+                    let expired = cache.remove(&tkey);
+                    self.inner.reindex(spec, &tkey, expired.as_ref().map(cached), None);
+                }
+            }
+        }
+
+        Ok(found)
+    }
+}
+
+pub(crate) struct MemoryEntryHandle<'a> {
+    cache: RwLockWriteGuard<'a, HashMap<String, MemCacheable>>,
+    tkey: String,
+    inner: &'a Inner,
+    spec: IndexSpec,
+    compression: Option<Compression>,
+}
+
+impl<'a> MemoryEntryHandle<'a> {
+    pub fn store<O: Cacheable + Clone + 'static>(&mut self, obj: O, keep_ttl: bool) -> Result<()> {
+        if keep_ttl {
+            if let Some(entry) = self.cache.get_mut(&self.tkey) {
+                let previous = std::mem::replace(&mut entry.0, boxed(obj, self.compression.as_ref())?);
+                entry.2 = self.inner.next_version();
+                self.inner.reindex(self.spec, &self.tkey, Some(&*previous), Some(&*entry.0));
+                return Ok(());
+            }
+        }
+
+        let exp = obj.expires_after().map(|ttl| { Expiration::new(ttl) });
+        replace_entry(&mut self.cache, self.inner, self.spec, &self.tkey, boxed(obj, self.compression.as_ref())?, exp);
+        Ok(())
+    }
+
+    pub fn delete(&mut self) -> Result<()> {
+        let previous = self.cache.remove(&self.tkey);
+        self.inner.reindex(self.spec, &self.tkey, previous.as_ref().map(cached), None);
         Ok(())
     }
 }
 
+fn live_object<O: Cacheable + Clone + 'static>(entry: Option<&MemCacheable>) -> Result<Option<O>> {
+    let &(ref obj, ref exp, _) = match entry {
+        Some(entry) => entry,
+        None => return Ok(None),
+    };
+
+    if let &Some(ref exp) = exp {
+        if exp.is_expired() {
+            return Ok(None);
+        }
+    }
+
+    unboxed::<O>(&**obj).map(Some)
+}
+
+// Stores `obj` as a new version of the entry at `tkey`, returning the previous entry and the new version.
+// An object which is already expired (a ttl of 0) removes the entry instead, leaving it at version 0.
+fn replace_entry(cache: &mut HashMap<String, MemCacheable>, inner: &Inner, spec: IndexSpec, tkey: &str, obj: Box<dyn Cacheable>, exp: Option<Expiration>) -> (Option<MemCacheable>, u64) {
+    let (previous, version) = if exp.as_ref().is_some_and(Expiration::is_expired) {
+        (cache.remove(tkey), 0)
+    } else {
+        let version = inner.next_version();
+        (cache.insert(tkey.to_string(), (obj, exp, version)), version)
+    };
+
+    inner.reindex(spec, tkey, previous.as_ref().map(cached), cache.get(tkey).map(cached));
+    (previous, version)
+}
+
+fn current_version(entry: Option<&MemCacheable>) -> u64 {
+    match entry {
+        Some(&(_, Some(ref exp), _)) if exp.is_expired() => 0,
+        Some(&(_, _, version)) => version,
+        None => 0,
+    }
+}
+
 fn gen_key<K: ToString, O: Cacheable>(key: K) -> String {
     format!("{}:{}", O::model_name(), key.to_string())
 }
 
+// A hash value or set member read as a string, which fails like redis does if it isn't UTF-8
+fn utf8(value: &[u8]) -> Result<String> {
+    String::from_utf8(value.to_vec()).map_err(|_| CacheError::Other("Value isn't valid UTF-8".to_string()))
+}
+
 impl CacheFunc for MemoryCache {
     fn hash_delete(&self, key: &str, fields: &[&str]) -> Result<bool> {
         let map = self.inner.hashsets.read();
@@ -187,16 +605,34 @@ impl CacheFunc for MemoryCache {
         let map = self.inner.hashsets.read();
         if let Some(hash) = map.get(key) {
             if let Some(val) = hash.read().get(field) {
-                return T::from_str(val).map(|t| Some(t)).map_err(|_| crate::CacheError::Other("Unable to parse value into desired type".to_string()));
+                return crate::parse_value(key, Some(field), &utf8(val)?).map(|t| Some(t));
             }
         }
         Ok(None)
     }
 
+    fn hash_get_bytes(&self, key: &str, field: &str) -> Result<Option<Vec<u8>>> {
+        let map = self.inner.hashsets.read();
+        if let Some(hash) = map.get(key) {
+            return Ok(hash.read().get(field).cloned());
+        }
+        Ok(None)
+    }
+
     fn hash_get_all<T: Cacheable + Clone + 'static>(&self, key: &str) -> Result<Option<T>> {
         self.get::<&str, T>(key)
     }
 
+    fn hash_get_map<T: FromStr>(&self, key: &str) -> Result<HashMap<String, T>> {
+        let map = self.inner.hashsets.read();
+        if let Some(hash) = map.get(key) {
+            return hash.read().iter().map(|(field, val)| {
+                crate::parse_value(key, Some(field), &utf8(val)?).map(|t| (field.clone(), t))
+            }).collect();
+        }
+        Ok(HashMap::new())
+    }
+
     fn hash_keys(&self, key: &str) -> Result<Vec<String>> {
         let map = self.inner.hashsets.read();
         if let Some(hash) = map.get(key) {
@@ -220,8 +656,10 @@ impl CacheFunc for MemoryCache {
         if let Some(hash) = map.get(key) {
             let reader = hash.read();
             for f in fields {
-                vec.push(reader.get(f.clone()).map(|s| s.clone()));
+                vec.push(reader.get(f.clone()).map(|s| utf8(s)).transpose()?);
             }
+        } else {
+            vec.resize(fields.len(), None);
         }
 
         Ok(vec)
@@ -233,7 +671,7 @@ impl CacheFunc for MemoryCache {
         if let Some(hash) = map.get(key) {
             let mut writer = hash.write();
             for pair in fv_pairs {
-                writer.insert(pair.0.to_string(), pair.1.to_string());
+                writer.insert(pair.0.to_string(), pair.1.to_string().into_bytes());
             }
             Ok(true)
         } else {
@@ -242,10 +680,14 @@ impl CacheFunc for MemoryCache {
     }
 
     fn hash_set<V: ToString>(&self, key: &str, field: &str, value: V) -> Result<bool> {
+        self.hash_set_bytes(key, field, value.to_string())
+    }
+
+    fn hash_set_bytes<V: AsRef<[u8]>>(&self, key: &str, field: &str, value: V) -> Result<bool> {
         self.inner.ensure_hash_exists(key)?;
         let map = self.inner.hashsets.read();
         if let Some(hash) = map.get(key) {
-            hash.write().insert(field.to_string(), value.to_string());
+            hash.write().insert(field.to_string(), value.as_ref().to_vec());
             Ok(true)
         } else {
             Err(crate::CacheError::Other("Unable to retrive hash from key".to_string()))
@@ -266,7 +708,7 @@ impl CacheFunc for MemoryCache {
                 }
             }
             {
-                hash.write().insert(field.to_string(), value.to_string());
+                hash.write().insert(field.to_string(), value.to_string().into_bytes());
                 Ok(true)
             }
         } else {
@@ -275,6 +717,10 @@ impl CacheFunc for MemoryCache {
     }
 
     fn hash_values(&self, key: &str) -> Result<Vec<String>> {
+        self.hash_values_bytes(key)?.iter().map(|v| utf8(v)).collect()
+    }
+
+    fn hash_values_bytes(&self, key: &str) -> Result<Vec<Vec<u8>>> {
         let map = self.inner.hashsets.read();
         let vec = if let Some(hash) = map.get(key) {
             hash.read().values().map(|s| s.clone()).collect()
@@ -286,12 +732,17 @@ impl CacheFunc for MemoryCache {
     }
 
     fn set_add<V: ToString>(&self, key: &str, members: &[V]) -> Result<bool> {
+        let members = members.iter().map(|m| m.to_string()).collect::<Vec<String>>();
+        self.set_add_bytes(key, &members)
+    }
+
+    fn set_add_bytes<V: AsRef<[u8]>>(&self, key: &str, members: &[V]) -> Result<bool> {
         self.inner.ensure_set_exists(key)?;
         let sets = self.inner.sets.read();
         if let Some(set) = sets.get(key) {
             let mut writer = set.write();
             for m in members {
-                writer.insert(m.to_string());
+                writer.insert(m.as_ref().to_vec());
             }
             Ok(true)
         } else {
@@ -316,7 +767,7 @@ impl CacheFunc for MemoryCache {
         if let Some(set) = siter.next() {
             let res = siter.fold(set.read().clone(), |diff_set, current_set_lock| {
                 diff_set.difference(&current_set_lock.read()).map(|sref| sref.clone()).collect()
-            }).iter().map(|sref| sref.clone()).collect::<Vec<_>>();
+            }).iter().map(|sref| utf8(sref)).collect::<Result<Vec<_>>>()?;
 
             Ok(res)
         } else {
@@ -336,7 +787,7 @@ impl CacheFunc for MemoryCache {
                 diff_set.difference(&current_set_lock.read()).map(|sref| sref.clone()).collect()
             }).iter().map(|sref| sref.clone()).collect::<Vec<_>>();
 
-            if let Ok(true) = self.set_add(diff_name, &res) {
+            if let Ok(true) = self.set_add_bytes(diff_name, &res) {
                 Ok(res.len() as u64)
             } else {
                 Ok(0)
@@ -355,7 +806,7 @@ impl CacheFunc for MemoryCache {
         if let Some(set) = siter.next() {
             let res = siter.fold(set.read().clone(), |inter_set, current_set_lock| {
                 inter_set.intersection(&current_set_lock.read()).map(|sref| sref.clone()).collect()
-            }).iter().map(|sref| sref.clone()).collect::<Vec<_>>();
+            }).iter().map(|sref| utf8(sref)).collect::<Result<Vec<_>>>()?;
 
             Ok(res)
         } else {
@@ -375,7 +826,7 @@ impl CacheFunc for MemoryCache {
                 inter_set.intersection(&current_set_lock.read()).map(|sref| sref.clone()).collect()
             }).iter().map(|sref| sref.clone()).collect::<Vec<_>>();
 
-            if let Ok(true) = self.set_add(inter_name, &res) {
+            if let Ok(true) = self.set_add_bytes(inter_name, &res) {
                 Ok(res.len() as u64)
             } else {
                 Ok(0)
@@ -386,25 +837,33 @@ impl CacheFunc for MemoryCache {
     }
 
     fn set_ismember<V: ToString>(&self, key: &str, member: V) -> Result<bool> {
+        self.set_ismember_bytes(key, member.to_string())
+    }
+
+    fn set_ismember_bytes<V: AsRef<[u8]>>(&self, key: &str, member: V) -> Result<bool> {
         let sets = self.inner.sets.read();
         if let Some(set) = sets.get(key) {
-            Ok(set.read().contains(&member.to_string()))
+            Ok(set.read().contains(member.as_ref()))
         } else {
             Ok(false)
         }
     }
 
     fn set_members(&self, key: &str) -> Result<Vec<String>> {
+        self.set_members_bytes(key)?.iter().map(|m| utf8(m)).collect()
+    }
+
+    fn set_members_bytes(&self, key: &str) -> Result<Vec<Vec<u8>>> {
         let sets = self.inner.sets.read();
         if let Some(set) = sets.get(key) {
-            Ok(set.read().iter().map(|ref_str| ref_str.clone()).collect::<Vec<String>>())
+            Ok(set.read().iter().map(|ref_str| ref_str.clone()).collect::<Vec<Vec<u8>>>())
         } else {
             Ok(vec![])
         }
     }
 
     fn set_move<V: ToString>(&self, key1: &str, key2: &str, member: V) -> Result<bool> {
-        let set_member = member.to_string();
+        let set_member = member.to_string().into_bytes();
         let sets = self.inner.sets.read();
         if let Some(set) = sets.get(key1) {
             let inserted = {
@@ -425,9 +884,13 @@ impl CacheFunc for MemoryCache {
     }
 
     fn set_rem<V: ToString>(&self, key: &str, member: V) -> Result<bool> {
+        self.set_rem_bytes(key, member.to_string())
+    }
+
+    fn set_rem_bytes<V: AsRef<[u8]>>(&self, key: &str, member: V) -> Result<bool> {
         let sets = self.inner.sets.read();
         if let Some(set) = sets.get(key) {
-            Ok(set.write().remove(&member.to_string()))
+            Ok(set.write().remove(member.as_ref()))
         } else {
             Ok(false)
         }
@@ -442,7 +905,7 @@ impl CacheFunc for MemoryCache {
         if let Some(set) = siter.next() {
             let res = siter.fold(set.read().clone(), |union_set, current_set_lock| {
                 union_set.union(&current_set_lock.read()).map(|sref| sref.clone()).collect()
-            }).iter().map(|sref| sref.clone()).collect::<Vec<_>>();
+            }).iter().map(|sref| utf8(sref)).collect::<Result<Vec<_>>>()?;
 
             Ok(res)
         } else {
@@ -462,7 +925,7 @@ impl CacheFunc for MemoryCache {
                 union_set.union(&current_set_lock.read()).map(|sref| sref.clone()).collect()
             }).iter().map(|sref| sref.clone()).collect::<Vec<_>>();
 
-            if let Ok(true) = self.set_add(union_name, &res) {
+            if let Ok(true) = self.set_add_bytes(union_name, &res) {
                 Ok(res.len() as u64)
             } else {
                 Ok(0)
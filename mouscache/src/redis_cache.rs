@@ -1,20 +1,240 @@
 use std::net;
 use std::mem::discriminant;
 use std::collections::hash_map::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::SystemTime;
 use crate::Result;
 use crate::CacheError;
 use crate::Cacheable;
 use crate::CacheAccess;
 use crate::CacheFunc;
+use crate::UpdateStatus;
+use crate::{EPOCH_FIELD, SCHEMA_FIELD, TOKEN_FIELD_PREFIX, VERSION_FIELD};
+use crate::Storage;
+use crate::codec;
+use crate::compression::{self, Compression};
+use crate::encryption;
+#[cfg(feature = "encryption")]
+use crate::encryption::{Encryption, KeyProvider};
+use crate::entry::{Entry, EntryHandle, OccupiedEntry, VacantEntry};
 use crate::redis;
-use redis::Commands;
+use redis::{Commands, PipelineCommands};
 use dns_lookup::lookup_host;
 use crate::FromValue;
 
-use r2d2::Pool;
+use r2d2::{Pool, PooledConnection};
 use std::str::FromStr;
 
 const DB_CONNECTION_TIMEOUT_MS: i64 = 5000;
+const MAX_TRANSACTION_RETRIES: usize = 100;
+
+// Index sets are named after the model, field and value (see `index_key`) and hold the keys of the matching hashes.
+// The scripts maintaining them read the indexed values from the hashes, so the index sets they touch can't be
+// declared in KEYS: indexes need a single redis instance and aren't supported with redis cluster.
+//
+// Lua helpers shared by these scripts. `index_key` builds the same keys as `crate::index_key`. `index_add` also
+// drops a few members picked at random which no longer hold the value, and keeps the set alive at least as long as
+// the object it adds (`ttl` in ms, nil if it doesn't expire): a set expires along with the last of its objects, but
+// once it holds an object which doesn't expire it's kept until its members are removed, by writes or by `find_by`.
+macro_rules! lua_index_functions {
+    () => {
+        r"
+local function index_key(model, field, value)
+    local function escape(part)
+        return (part:gsub('\\', '\\\\'):gsub('%z', '\\0'))
+    end
+    return model .. '\0idx\0' .. escape(field) .. '\0' .. escape(value)
+end
+local function index_add(model, field, value, key, ttl)
+    local index = index_key(model, field, value)
+    for _, member in ipairs(redis.call('SRANDMEMBER', index, 3)) do
+        if redis.call('HGET', member, field) ~= value then
+            redis.call('SREM', index, member)
+        end
+    end
+    local left = redis.call('PTTL', index)
+    redis.call('SADD', index, key)
+    if not ttl then
+        redis.call('PERSIST', index)
+    elseif left == -2 or (left >= 0 and left < ttl) then
+        redis.call('PEXPIRE', index, ttl)
+    end
+end
+local function index_remove(model, field, value, key)
+    redis.call('SREM', index_key(model, field, value), key)
+end
+"
+    };
+}
+
+// Replaces the hash stored at KEYS[1], bumps its version field and moves it to the indexes of its new values.
+// A new hash gets the epoch ARGV[5], so that its versions differ from those of a hash deleted from the same key.
+// ARGV[1]: version field, ARGV[2]: epoch field, ARGV[3]: ttl (see `ttl_arg`),
+// ARGV[4]: expected current `epoch:version` or an empty string, ARGV[5]: epoch of a new hash, ARGV[6]: model name,
+// ARGV[7]: number of indexed fields, followed by the indexed fields and the field/value pairs.
+// Returns the new version and the epoch, the version being 0 if a ttl of 0 deleted the hash,
+// or -1 if the current one doesn't match the expected one.
+const OBJECT_WRITE_SCRIPT: &str = concat!(lua_index_functions!(), r"
+local version = tonumber(redis.call('HGET', KEYS[1], ARGV[1]) or '0')
+local epoch = redis.call('HGET', KEYS[1], ARGV[2]) or '0'
+if ARGV[4] ~= '' and ARGV[4] ~= epoch .. ':' .. version then
+    return {-1, epoch}
+end
+if version == 0 then
+    epoch = ARGV[5]
+end
+local ttl = nil
+if ARGV[3] == 'keep' then
+    local left = redis.call('PTTL', KEYS[1])
+    if left > 0 then
+        ttl = left
+    end
+elseif ARGV[3] ~= '' then
+    ttl = tonumber(ARGV[3]) * 1000
+end
+local data = 8 + tonumber(ARGV[7])
+for i = 8, data - 1 do
+    local value = redis.call('HGET', KEYS[1], ARGV[i])
+    if value then
+        index_remove(ARGV[6], ARGV[i], value, KEYS[1])
+    end
+end
+redis.call('DEL', KEYS[1])
+if ttl == 0 then
+    return {0, epoch}
+end
+if #ARGV >= data then
+    redis.call('HMSET', KEYS[1], unpack(ARGV, data))
+end
+for i = 8, data - 1 do
+    local value = redis.call('HGET', KEYS[1], ARGV[i])
+    if value then
+        index_add(ARGV[6], ARGV[i], value, KEYS[1], ttl)
+    end
+end
+version = version % 4294967295 + 1
+redis.call('HMSET', KEYS[1], ARGV[1], version, ARGV[2], epoch)
+if ttl then
+    redis.call('PEXPIRE', KEYS[1], ttl)
+end
+return {version, epoch}
+");
+
+// Blobs split in chunks (see `split_chunks`) are stored as a manifest prefixed by byte 253 and starting
+// with the number of chunks, which are stored at `{key}\0chunk\0N` (see `chunk_key`). The hash tag keeps
+// the chunks in the cluster slot of their manifest, and the NUL bytes apart from object keys.
+
+// Deletes the object stored at KEYS[1], removing it from its indexes or deleting its chunks.
+// ARGV[1]: model name, ARGV[2..]: indexed fields.
+const OBJECT_DELETE_SCRIPT: &str = concat!(lua_index_functions!(), r"
+for i = 2, #ARGV do
+    local value = redis.call('HGET', KEYS[1], ARGV[i])
+    if value then
+        index_remove(ARGV[1], ARGV[i], value, KEYS[1])
+    end
+end
+if redis.call('TYPE', KEYS[1]).ok == 'string' then
+    local chunks = tonumber(string.match(redis.call('GET', KEYS[1]), '^\253(%d+):') or '0')
+    for i = 0, chunks - 1 do
+        redis.call('DEL', '{' .. KEYS[1] .. '}\0chunk\0' .. i)
+    end
+end
+return redis.call('DEL', KEYS[1])
+");
+
+// Sets or deletes a single field of the hash stored at KEYS[1] if it exists, bumping its version field.
+// ARGV[1]: version field, ARGV[2]: schema field, ARGV[3]: schema version of the model,
+// ARGV[4]: model name if the field is indexed or an empty string, ARGV[5]: field, ARGV[6]: value (the field is deleted when missing).
+// Returns 1 if the field was written, 0 if there is no hash, or -1 if the hash was written under another schema version.
+const FIELD_WRITE_SCRIPT: &str = concat!(lua_index_functions!(), r"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    return 0
+end
+if tonumber(redis.call('HGET', KEYS[1], ARGV[2]) or '0') ~= tonumber(ARGV[3]) then
+    return -1
+end
+if ARGV[4] ~= '' then
+    local value = redis.call('HGET', KEYS[1], ARGV[5])
+    if value then
+        index_remove(ARGV[4], ARGV[5], value, KEYS[1])
+    end
+end
+if #ARGV > 5 then
+    redis.call('HSET', KEYS[1], ARGV[5], ARGV[6])
+    if ARGV[4] ~= '' then
+        local left = redis.call('PTTL', KEYS[1])
+        index_add(ARGV[4], ARGV[5], ARGV[6], KEYS[1], left > 0 and left or nil)
+    end
+else
+    redis.call('HDEL', KEYS[1], ARGV[5])
+end
+redis.call('HSET', KEYS[1], ARGV[1], tonumber(redis.call('HGET', KEYS[1], ARGV[1]) or '0') % 4294967295 + 1)
+return 1
+");
+
+// Replaces the string stored at KEYS[1] and its chunks, using PSETEX when they expire.
+// KEYS[2..]: keys of the new chunks, ARGV[1]: value or chunk manifest, ARGV[2]: ttl (see `ttl_arg`), ARGV[3..]: chunks.
+const BLOB_WRITE_SCRIPT: &str = r"
+local ttl = nil
+if ARGV[2] == 'keep' then
+    local left = redis.call('PTTL', KEYS[1])
+    if left > 0 then
+        ttl = left
+    end
+elseif ARGV[2] ~= '' then
+    ttl = tonumber(ARGV[2]) * 1000
+end
+if redis.call('TYPE', KEYS[1]).ok == 'string' then
+    local chunks = tonumber(string.match(redis.call('GET', KEYS[1]), '^\253(%d+):') or '0')
+    for i = 0, chunks - 1 do
+        redis.call('DEL', '{' .. KEYS[1] .. '}\0chunk\0' .. i)
+    end
+end
+if ttl == 0 then
+    return redis.call('DEL', KEYS[1])
+end
+local function set(key, value)
+    if ttl then
+        redis.call('PSETEX', key, ttl, value)
+    else
+        redis.call('SET', key, value)
+    end
+end
+set(KEYS[1], ARGV[1])
+for i = 3, #ARGV do
+    set(KEYS[i - 1], ARGV[i])
+end
+return 0
+";
+
+// Returns the string stored at KEYS[1] followed by its chunks, or nothing if there is none.
+const BLOB_READ_SCRIPT: &str = r"
+local value = redis.call('GET', KEYS[1])
+if not value then
+    return {}
+end
+local parts = {value}
+local chunks = tonumber(string.match(value, '^\253(%d+):') or '0')
+for i = 0, chunks - 1 do
+    table.insert(parts, redis.call('GET', '{' .. KEYS[1] .. '}\0chunk\0' .. i) or '')
+end
+return parts
+";
+
+// Returns the keys of the index set KEYS[1] whose hash field ARGV[1] is still ARGV[2],
+// removing the others (expired, deleted or rewritten since) from the set.
+const INDEX_READ_SCRIPT: &str = r"
+local keys = {}
+for _, key in ipairs(redis.call('SMEMBERS', KEYS[1])) do
+    if redis.call('HGET', key, ARGV[1]) == ARGV[2] then
+        table.insert(keys, key)
+    else
+        redis.call('SREM', KEYS[1], key)
+    end
+end
+return keys
+";
 
 mod r2d2_test {
     use crate::redis;
@@ -103,14 +323,145 @@ mod r2d2_test {
 #[allow(dead_code)]
 pub struct RedisCache {
     connection_pool: Pool<r2d2_test::RedisConnectionManager>,
+    values: ValueFormat,
 }
 
 impl Clone for RedisCache {
     fn clone(&self) -> Self {
         RedisCache {
-            connection_pool: self.connection_pool.clone()
+            connection_pool: self.connection_pool.clone(),
+            values: self.values.clone(),
+        }
+    }
+}
+
+// How object values are written, see `compression` and `encryption`
+#[derive(Clone, Default)]
+struct ValueFormat {
+    compression: Option<Compression>,
+    // Size in bytes above which blobs are split in chunks
+    chunk_size: Option<usize>,
+    #[cfg(feature = "encryption")]
+    encryption: Option<Encryption>,
+    // Whether values without the encryption header are read as is, see `RedisCache::allow_plaintext_reads`
+    #[cfg(feature = "encryption")]
+    plaintext_reads: bool,
+}
+
+impl ValueFormat {
+    fn encode(&self, value: String, redis_key: &str, field: &str) -> Vec<u8> {
+        let value = compression::compress(self.compression.as_ref(), value);
+        self.encrypt(value, redis_key, field)
+    }
+
+    fn decode(&self, raw: Vec<u8>, redis_key: &str, field: &str) -> Result<String> {
+        let raw = if raw.first() == Some(&encryption::HEADER) {
+            self.decrypt(&raw, redis_key, field)?
+        } else if self.refuses_plaintext() {
+            return Err(CacheError::DecryptionError(match field {
+                "" => format!("{} isn't encrypted", redis_key),
+                field => format!("Field {} of {} isn't encrypted", field, redis_key),
+            }));
+        } else {
+            raw
+        };
+        compression::decompress(raw)
+    }
+
+    // Reads a field of a hash, the reserved fields are never decrypted
+    fn decode_field(&self, raw: Vec<u8>, redis_key: &str, field: &str) -> Result<String> {
+        if is_reserved(field) {
+            compression::decompress(raw)
+        } else {
+            self.decode(raw, redis_key, field)
+        }
+    }
+
+    // Fields of an `O` hash which redis has to read, and are therefore neither compressed nor encrypted.
+    // Indexed fields are encrypted along with the others when there's a key provider, their token is stored instead.
+    fn stored_in_clear<O: Cacheable>(&self, field: &str) -> bool {
+        is_reserved(field) || (!self.encrypts() && O::indexed_fields().contains(&field))
+    }
+
+    // Hash fields the indexes of `O` are kept on
+    fn index_fields<O: Cacheable>(&self) -> Vec<String> {
+        O::indexed_fields().iter().map(|field| self.index_field(field)).collect()
+    }
+
+    // Hash field and value an index of `field` is kept on, see `index_fields`
+    fn index_field(&self, field: &str) -> String {
+        if self.encrypts() {
+            format!("{}{}", TOKEN_FIELD_PREFIX, field)
+        } else {
+            field.to_string()
+        }
+    }
+
+    fn index_value<O: Cacheable>(&self, field: &str, value: String) -> String {
+        self.token::<O>(field, &value).unwrap_or(value)
+    }
+
+    #[cfg(feature = "encryption")]
+    fn encrypts(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    fn encrypts(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "encryption")]
+    fn token<O: Cacheable>(&self, field: &str, value: &str) -> Option<String> {
+        self.encryption.as_ref().map(|encryption| encryption.token(format!("{}\0{}\0{}", O::model_name(), field, value).as_bytes()))
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    fn token<O: Cacheable>(&self, _field: &str, _value: &str) -> Option<String> {
+        None
+    }
+
+    #[cfg(feature = "encryption")]
+    fn refuses_plaintext(&self) -> bool {
+        self.encryption.is_some() && !self.plaintext_reads
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    fn refuses_plaintext(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "encryption")]
+    fn encrypt(&self, value: Vec<u8>, redis_key: &str, field: &str) -> Vec<u8> {
+        match self.encryption {
+            Some(ref encryption) => encryption.seal(&value, sealed_as(redis_key, field).as_bytes()),
+            None => value,
         }
     }
+
+    #[cfg(not(feature = "encryption"))]
+    fn encrypt(&self, value: Vec<u8>, _redis_key: &str, _field: &str) -> Vec<u8> {
+        value
+    }
+
+    #[cfg(feature = "encryption")]
+    fn decrypt(&self, raw: &[u8], redis_key: &str, field: &str) -> Result<Vec<u8>> {
+        match self.encryption {
+            Some(ref encryption) => encryption.open(raw, sealed_as(redis_key, field).as_bytes()),
+            None => Err(CacheError::DecryptionError(format!("{} is encrypted but the cache has no key provider", redis_key))),
+        }
+    }
+
+    #[cfg(not(feature = "encryption"))]
+    fn decrypt(&self, _raw: &[u8], redis_key: &str, _field: &str) -> Result<Vec<u8>> {
+        Err(CacheError::DecryptionError(format!("{} is encrypted, which requires the encryption feature", redis_key)))
+    }
+}
+
+// Data authenticated along with an encrypted value, binding it to its entry
+#[cfg(feature = "encryption")]
+fn sealed_as(redis_key: &str, field: &str) -> String {
+    format!("{}\0{}", redis_key, field)
 }
 
 impl RedisCache {
@@ -150,11 +501,96 @@ impl RedisCache {
 
             return Ok(RedisCache {
                 connection_pool,
+                values: ValueFormat::default(),
             });
         }
 
         Err(CacheError::Other(format!("Could'n find any valid IP for host {} ", host)))
     }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.values.compression = Some(compression);
+        self
+    }
+
+    pub fn with_chunking(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        self.values.chunk_size = Some(chunk_size);
+        self
+    }
+
+    #[cfg(feature = "encryption")]
+    pub fn with_encryption<P: KeyProvider + 'static>(mut self, keys: P) -> Self {
+        self.values.encryption = Some(Encryption::new(keys));
+        self
+    }
+
+    #[cfg(feature = "encryption")]
+    pub fn allow_plaintext_reads(mut self) -> Self {
+        self.values.plaintext_reads = true;
+        self
+    }
+
+    // Sets or deletes (when `value` is `None`) a single field of an existing entry. Entries written under
+    // another schema are migrated and rewritten whole, or left alone if they can't be.
+    fn write_field<K: ToString, O: Cacheable + 'static>(&self, key: K, field: &str, value: Option<String>) -> Result<bool> {
+        crate::ensure_hash_storage::<O>("single field access")?;
+
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+
+        let redis_key = redis_key_create::<K, O>(key);
+        let index = index_prefix::<O>(field);
+        // Encrypted indexed fields are written along with their token, by rewriting the object
+        let rewrite = !index.is_empty() && self.values.encrypts();
+
+        for _ in 0..MAX_TRANSACTION_RETRIES {
+            if !rewrite {
+                let mut script = redis::cmd("EVAL");
+                script.arg(FIELD_WRITE_SCRIPT)
+                    .arg(1)
+                    .arg(&redis_key)
+                    .arg(VERSION_FIELD)
+                    .arg(SCHEMA_FIELD)
+                    .arg(O::schema_version())
+                    .arg(index)
+                    .arg(field);
+                if let Some(ref value) = value {
+                    if index.is_empty() {
+                        script.arg(self.values.encode(value.clone(), &redis_key, field));
+                    } else {
+                        script.arg(value);
+                    }
+                }
+
+                match script.query::<i64>(&*connection)? {
+                    -1 => {}
+                    written => return Ok(written > 0),
+                }
+            }
+
+            let (mut val, version) = match object_data::<O>(object_get::<O>(&connection, &redis_key, &self.values)?) {
+                Some(data) => data,
+                None => return Ok(false),
+            };
+            match value {
+                Some(ref value) => val.insert(field.to_string(), value.clone()),
+                None => val.remove(field),
+            };
+
+            let obj = O::from_redis_obj(val)?;
+            let data = stored_values::<O>(&redis_key, object_fields(&obj)?, &self.values);
+            let (written,): ((i64, u32),) = object_write::<O>(&mut redis::pipe(), &redis_key, &data, &self.values.index_fields::<O>(), None, true, Some(version))
+                .query(&*connection)?;
+            if written_version(written).is_some() {
+                return Ok(true);
+            }
+        }
+
+        Err(CacheError::AccessError(format!("Unable to write field {} of {} after {} retries", field, redis_key, MAX_TRANSACTION_RETRIES)))
+    }
 }
 
 impl CacheAccess for RedisCache {
@@ -170,12 +606,10 @@ impl CacheAccess for RedisCache {
         };
 
         let redis_key = redis_key_create::<K, O>(key);
-        let data = obj.to_redis_obj();
-        if let Some(ttl) = expires_after {
-            redis_hash_set_multiple_with_expire(&connection, redis_key, &data, ttl)
-        } else {
-            redis_hash_set_multiple(&connection, redis_key, &data)
-        }
+
+        object_store(&mut redis::pipe(), &redis_key, &obj, expires_after, false, &self.values)?.ignore()
+            .query(&*connection)
+            .map_err(|e| e.into())
     }
 
     fn get<K: ToString, O: Cacheable + 'static>(&self, key: K) -> Result<Option<O>> {
@@ -185,14 +619,10 @@ impl CacheAccess for RedisCache {
         };
 
         let redis_key = redis_key_create::<K, O>(key);
-        if let Ok(val) = redis_hash_get_all(&connection, redis_key) {
-            if let Ok(c) = O::from_redis_obj(val) {
-                Ok(Some(c))
-            } else {
-                Ok(None)
-            }
-        } else {
-            Ok(None)
+        match object_get::<O>(&connection, &redis_key, &self.values) {
+            Ok(val) => Ok(object_from_hash::<O>(val)),
+            Err(e @ CacheError::DecryptionError(_)) | Err(e @ CacheError::IntegrityError(_)) => Err(e),
+            Err(_) => Ok(None),
         }
     }
 
@@ -214,29 +644,491 @@ impl CacheAccess for RedisCache {
         };
 
         let redis_key = redis_key_create::<K, O>(key);
-        redis_delete(&connection, redis_key)
+        object_delete(&mut redis::pipe(), &redis_key, O::model_name(), &self.values.index_fields::<O>()).ignore()
+            .query(&*connection)
+            .map_err(|e| e.into())
+    }
+
+    fn take<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Option<O>> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+
+        let redis_key = redis_key_create::<K, O>(key);
+
+        let mut pipe = redis::pipe();
+        object_read::<O>(pipe.atomic(), &redis_key);
+        let (val,): (redis::Value,) = object_delete(&mut pipe, &redis_key, O::model_name(), &self.values.index_fields::<O>()).ignore()
+            .query(&*connection)?;
+
+        Ok(object_from_hash::<O>(stored_fields::<O>(val, &redis_key, &self.values)?))
+    }
+
+    fn swap<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K, obj: O) -> Result<Option<O>> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+
+        let redis_key = redis_key_create::<K, O>(key);
+
+        let mut pipe = redis::pipe();
+        object_read::<O>(pipe.atomic(), &redis_key);
+
+        let (val,): (redis::Value,) = object_store(&mut pipe, &redis_key, &obj, obj.expires_after(), false, &self.values)?.ignore()
+            .query(&*connection)?;
+
+        Ok(object_from_hash::<O>(stored_fields::<O>(val, &redis_key, &self.values)?))
+    }
+
+    fn update<K: ToString, O: Cacheable + Clone + 'static, F: FnMut(&mut O)>(&self, key: K, mut f: F) -> Result<UpdateStatus> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+
+        // The handle owns the connection and will UNWATCH it when dropped, whichever way this returns
+        let handle = RedisEntryHandle {
+            connection,
+            redis_key: redis_key_create::<K, O>(key),
+            model: O::model_name(),
+            indexed: self.values.index_fields::<O>(),
+            values: self.values.clone(),
+        };
+
+        for retries in 0..MAX_TRANSACTION_RETRIES {
+            redis::cmd("WATCH").arg(&handle.redis_key).query::<()>(&*handle.connection)?;
+
+            let val = match object_data::<O>(object_get::<O>(&handle.connection, &handle.redis_key, &handle.values)?) {
+                Some((val, _)) => val,
+                None => return Ok(UpdateStatus { existed: false, retries }),
+            };
+
+            let mut obj = O::from_redis_obj(val)?;
+
+            f(&mut obj);
+
+            let mut pipe = redis::pipe();
+            pipe.atomic();
+
+            let committed: Option<()> = object_store(&mut pipe, &handle.redis_key, &obj, None, true, &handle.values)?.ignore()
+                .query(&*handle.connection)?;
+            if committed.is_some() {
+                return Ok(UpdateStatus { existed: true, retries });
+            }
+        }
+
+        Err(CacheError::AccessError(format!("Unable to update {} after {} retries", handle.redis_key, MAX_TRANSACTION_RETRIES)))
+    }
+
+    fn entry<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Entry<'_, O>> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+
+        let key = key.to_string();
+        let redis_key = redis_key_create::<&str, O>(&key);
+
+        redis::cmd("WATCH").arg(&redis_key).query::<()>(&*connection)?;
+
+        // From here on the handle owns the connection and will UNWATCH it when dropped
+        let handle = RedisEntryHandle {
+            connection,
+            redis_key,
+            model: O::model_name(),
+            indexed: self.values.index_fields::<O>(),
+            values: self.values.clone(),
+        };
+
+        let val = match object_data::<O>(object_get::<O>(&handle.connection, &handle.redis_key, &handle.values)?) {
+            Some((val, _)) => val,
+            None => return Ok(Entry::Vacant(VacantEntry::new(EntryHandle::Redis(Box::new(handle)), key))),
+        };
+
+        let obj = O::from_redis_obj(val)?;
+
+        Ok(Entry::Occupied(OccupiedEntry::new(EntryHandle::Redis(Box::new(handle)), key, obj)))
+    }
+
+    fn get_versioned<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K) -> Result<Option<(O, u64)>> {
+        crate::ensure_hash_storage::<O>("versions")?;
+
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+
+        let redis_key = redis_key_create::<K, O>(key);
+        let (val, version) = match object_data::<O>(object_get::<O>(&connection, &redis_key, &self.values)?) {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+
+        O::from_redis_obj(val).map(|obj| Some((obj, version)))
+    }
+
+    fn insert_if_version<K: ToString, O: Cacheable + Clone + 'static>(&self, key: K, obj: O, version: u64) -> Result<u64> {
+        crate::ensure_hash_storage::<O>("versions")?;
+
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+
+        let redis_key = redis_key_create::<K, O>(key);
+        let data = stored_values::<O>(&redis_key, object_fields(&obj)?, &self.values);
+
+        let (written,): ((i64, u32),) = object_write::<O>(&mut redis::pipe(), &redis_key, &data, &self.values.index_fields::<O>(), obj.expires_after(), false, Some(version))
+            .query(&*connection)?;
+
+        written_version(written)
+            .ok_or_else(|| CacheError::ConflictError(format!("{} is no longer at version {}", redis_key, version)))
+    }
+
+    fn get_field<K: ToString, O: Cacheable + 'static>(&self, key: K, field: &str) -> Result<Option<String>> {
+        crate::ensure_hash_storage::<O>("single field access")?;
+
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+
+        let redis_key = redis_key_create::<K, O>(key);
+        let (value, schema): (Option<Vec<u8>>, Option<u32>) = redis::cmd("HMGET").arg(&redis_key).arg(field).arg(SCHEMA_FIELD)
+            .query(&*connection)?;
+
+        // Entries written under another schema are read whole, to be migrated like `get` does
+        if schema.unwrap_or(0) != O::schema_version() {
+            return Ok(object_data::<O>(object_get::<O>(&connection, &redis_key, &self.values)?)
+                .and_then(|(mut val, _)| val.remove(field)));
+        }
+
+        value.map(|value| self.values.decode_field(value, &redis_key, field)).transpose()
+    }
+
+    fn set_field<K: ToString, O: Cacheable + 'static, V: ToString>(&self, key: K, field: &str, value: V) -> Result<bool> {
+        self.write_field::<K, O>(key, field, Some(value.to_string()))
+    }
+
+    fn delete_field<K: ToString, O: Cacheable + 'static>(&self, key: K, field: &str) -> Result<bool> {
+        self.write_field::<K, O>(key, field, None)
+    }
+
+    fn find_by<O: Cacheable + Clone + 'static, V: ToString>(&self, field: &str, value: V) -> Result<Vec<O>> {
+        crate::ensure_hash_storage::<O>("indexes")?;
+        crate::ensure_indexed::<O>(field)?;
+
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+
+        let index_field = self.values.index_field(field);
+        let index_value = self.values.index_value::<O>(field, value.to_string());
+        let redis_keys: Vec<String> = redis::cmd("EVAL")
+            .arg(INDEX_READ_SCRIPT)
+            .arg(1)
+            .arg(crate::index_key(O::model_name(), &index_field, &index_value))
+            .arg(&index_field)
+            .arg(&index_value)
+            .query(&*connection)?;
+
+        if redis_keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut pipe = redis::pipe();
+        for redis_key in &redis_keys {
+            pipe.hgetall(redis_key);
+        }
+        let vals: Vec<HashMap<String, Vec<u8>>> = pipe.query(&*connection)?;
+
+        let mut found = Vec::new();
+        for (redis_key, val) in redis_keys.iter().zip(vals) {
+            found.extend(object_from_hash::<O>(decode_fields(redis_key, val, &self.values)?));
+        }
+        Ok(found)
+    }
+}
+
+pub(crate) struct RedisEntryHandle {
+    connection: PooledConnection<r2d2_test::RedisConnectionManager>,
+    redis_key: String,
+    model: &'static str,
+    indexed: Vec<String>,
+    values: ValueFormat,
+}
+
+impl RedisEntryHandle {
+    pub fn store<O: Cacheable + Clone + 'static>(&mut self, obj: O, keep_ttl: bool) -> Result<()> {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        object_store(&mut pipe, &self.redis_key, &obj, obj.expires_after(), keep_ttl, &self.values)?.ignore();
+
+        self.exec(&pipe)
+    }
+
+    pub fn delete(&mut self) -> Result<()> {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        object_delete(&mut pipe, &self.redis_key, self.model, &self.indexed).ignore();
+
+        self.exec(&pipe)
+    }
+
+    fn exec(&mut self, pipe: &redis::Pipeline) -> Result<()> {
+        let committed: Option<()> = pipe.query(&*self.connection)?;
+        if committed.is_none() {
+            return Err(CacheError::ConflictError(format!("{} was modified concurrently", self.redis_key)));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for RedisEntryHandle {
+    fn drop(&mut self) {
+        let _ = redis_unwatch(&self.connection);
+    }
+}
+
+fn object_from_hash<O: Cacheable>(val: HashMap<String, String>) -> Option<O> {
+    object_data::<O>(val).and_then(|(val, _)| O::from_redis_obj(val).ok())
+}
+
+// Strips the reserved fields of a stored hash, returning its fields and version.
+// `None` when the hash is empty or was written under a schema `O` can't read.
+fn object_data<O: Cacheable>(mut val: HashMap<String, String>) -> Option<(HashMap<String, String>, u64)> {
+    if val.is_empty() {
+        return None;
+    }
+
+    let version = take_version(&mut val);
+    let schema = val.remove(SCHEMA_FIELD).and_then(|v| v.parse().ok()).unwrap_or(0);
+    val.retain(|field, _| !field.starts_with(TOKEN_FIELD_PREFIX));
+
+    let current = O::schema_version();
+    if schema == current {
+        Some((val, version))
+    } else if schema < current {
+        O::migrate(schema, val).map(|val| (val, version))
+    } else {
+        None
+    }
+}
+
+// Fields of `obj` as written to redis, tagged with its schema version
+fn object_fields<O: Cacheable>(obj: &O) -> Result<Vec<(String, String)>> {
+    let mut data = obj.try_to_redis_obj()?;
+    let schema = O::schema_version();
+    if schema > 0 {
+        data.push((SCHEMA_FIELD.to_string(), schema.to_string()));
+    }
+    Ok(data)
+}
+
+// Values of `data` as written to redis, encoded unless redis has to read them, along with the index tokens
+// of the indexed fields when encrypting
+fn stored_values<O: Cacheable>(redis_key: &str, data: Vec<(String, String)>, values: &ValueFormat) -> Vec<(String, Vec<u8>)> {
+    let mut stored = Vec::with_capacity(data.len());
+    for (field, value) in data {
+        if let Some(token) = index_token::<O>(&field, &value, values) {
+            stored.push((values.index_field(&field), token.into_bytes()));
+        }
+        let value = if values.stored_in_clear::<O>(&field) {
+            value.into_bytes()
+        } else {
+            values.encode(value, redis_key, &field)
+        };
+        stored.push((field, value));
+    }
+    stored
+}
+
+// Token stored next to the indexed `field` when encrypting
+fn index_token<O: Cacheable>(field: &str, value: &str, values: &ValueFormat) -> Option<String> {
+    if O::indexed_fields().contains(&field) {
+        values.token::<O>(field, value)
+    } else {
+        None
+    }
+}
+
+// Fields mouscache maintains next to the object fields
+fn is_reserved(field: &str) -> bool {
+    field == VERSION_FIELD || field == EPOCH_FIELD || field == SCHEMA_FIELD || field.starts_with(TOKEN_FIELD_PREFIX)
+}
+
+fn decode_fields(redis_key: &str, val: HashMap<String, Vec<u8>>, values: &ValueFormat) -> Result<HashMap<String, String>> {
+    val.into_iter().map(|(field, value)| {
+        let value = values.decode_field(value, redis_key, &field)?;
+        Ok((field, value))
+    }).collect()
+}
+
+// Versions are the epoch of a hash in the high 32 bits and the number of times it was written in the low ones.
+// Hashes written before epochs were added have an epoch of 0.
+fn take_version(val: &mut HashMap<String, String>) -> u64 {
+    let epoch: u64 = val.remove(EPOCH_FIELD).and_then(|v| v.parse().ok()).unwrap_or(0);
+    let version: u64 = val.remove(VERSION_FIELD).and_then(|v| v.parse().ok()).unwrap_or(0);
+    epoch << 32 | version & 0xFFFF_FFFF
+}
+
+// The version returned by OBJECT_WRITE_SCRIPT, 0 if the hash was deleted or `None` if the expected version didn't match
+fn written_version((version, epoch): (i64, u32)) -> Option<u64> {
+    match version {
+        version if version < 0 => None,
+        0 => Some(0),
+        version => Some((epoch as u64) << 32 | version as u64),
     }
 }
 
+// Random epoch of a new hash, see OBJECT_WRITE_SCRIPT
+fn new_epoch() -> u32 {
+    (RandomState::new().hash_one(SystemTime::now()) as u32).max(1)
+}
+
+fn object_write<'a, O: Cacheable>(pipe: &'a mut redis::Pipeline, redis_key: &str, data: &[(String, Vec<u8>)], indexed: &[String], ttl: Option<usize>, keep_ttl: bool, expected_version: Option<u64>) -> &'a mut redis::Pipeline {
+    let version_arg = expected_version.map(|v| format!("{}:{}", v >> 32, v & 0xFFFF_FFFF)).unwrap_or_default();
+
+    pipe.cmd("EVAL")
+        .arg(OBJECT_WRITE_SCRIPT)
+        .arg(1)
+        .arg(redis_key)
+        .arg(VERSION_FIELD)
+        .arg(EPOCH_FIELD)
+        .arg(ttl_arg(ttl, keep_ttl))
+        .arg(version_arg)
+        .arg(new_epoch())
+        .arg(O::model_name())
+        .arg(indexed.len())
+        .arg(indexed)
+        .arg(data)
+}
+
+// The ttl argument of the write scripts: the ttl in sec, an empty string for none or `keep` to keep the current one.
+// A ttl of 0 deletes the object instead, like it expired right away.
+fn ttl_arg(ttl: Option<usize>, keep_ttl: bool) -> String {
+    match ttl {
+        _ if keep_ttl => "keep".to_string(),
+        Some(ttl) => ttl.to_string(),
+        None => String::new(),
+    }
+}
+
+fn object_delete<'a>(pipe: &'a mut redis::Pipeline, redis_key: &str, model: &str, indexed: &[String]) -> &'a mut redis::Pipeline {
+    pipe.cmd("EVAL")
+        .arg(OBJECT_DELETE_SCRIPT)
+        .arg(1)
+        .arg(redis_key)
+        .arg(model)
+        .arg(indexed)
+}
+
 fn redis_key_create<K: ToString, O: Cacheable>(key: K) -> String {
     format!("{}:{}", O::model_name(), key.to_string())
 }
 
-fn redis_hash_set_multiple_with_expire<F: redis::ToRedisArgs, V: redis::ToRedisArgs>(con: &redis::Connection, key: String, v: &[(F, V)], ttl_sec: usize) -> Result<()> {
-    redis_hash_set_multiple(con, key.clone(), v)?;
-    con.expire(key, ttl_sec).map(|_: ::redis::Value| ()).map_err( |e| e.into())
+// Writes `obj` with the storage layout of its type, see `Cacheable::storage`
+fn object_store<'a, O: Cacheable>(pipe: &'a mut redis::Pipeline, redis_key: &str, obj: &O, ttl: Option<usize>, keep_ttl: bool, values: &ValueFormat) -> Result<&'a mut redis::Pipeline> {
+    let data = object_fields(obj)?;
+    Ok(match O::storage() {
+        Storage::Hash => object_write::<O>(pipe, redis_key, &stored_values::<O>(redis_key, data, values), &values.index_fields::<O>(), ttl, keep_ttl, None),
+        Storage::Blob => {
+            let blob = codec::encode_map(data.iter().map(|(k, v)| (k, v)));
+            let (blob, chunks) = split_chunks(values.encode(blob, redis_key, ""), values.chunk_size);
+            let chunk_keys: Vec<String> = (0..chunks.len()).map(|i| chunk_key(redis_key, i)).collect();
+            pipe.cmd("EVAL")
+                .arg(BLOB_WRITE_SCRIPT)
+                .arg(1 + chunks.len())
+                .arg(redis_key)
+                .arg(chunk_keys)
+                .arg(blob)
+                .arg(ttl_arg(ttl, keep_ttl))
+                .arg(chunks)
+        }
+    })
+}
+
+// Reads the object stored at `redis_key`, its result is turned into fields by `stored_fields`
+fn object_read<'a, O: Cacheable>(pipe: &'a mut redis::Pipeline, redis_key: &str) -> &'a mut redis::Pipeline {
+    match O::storage() {
+        Storage::Hash => pipe.hgetall(redis_key),
+        Storage::Blob => pipe.cmd("EVAL").arg(BLOB_READ_SCRIPT).arg(1).arg(redis_key),
+    }
+}
+
+// Stored fields of an object read with `object_read`, empty if there is none
+fn stored_fields<O: Cacheable>(val: redis::Value, redis_key: &str, values: &ValueFormat) -> Result<HashMap<String, String>> {
+    match O::storage() {
+        Storage::Hash => decode_fields(redis_key, HashMap::from_redis_value(&val)?, values),
+        Storage::Blob => {
+            let parts = Vec::<Vec<u8>>::from_redis_value(&val)?;
+            if parts.is_empty() {
+                return Ok(HashMap::new());
+            }
+            codec::decode_map(&values.decode(join_chunks(redis_key, parts)?, redis_key, "")?)
+        }
+    }
+}
+
+const CHUNKED: u8 = 0xFD;
+
+// The blob to store at the key of `blob` and its chunks, if it's over `chunk_size`
+fn split_chunks(blob: Vec<u8>, chunk_size: Option<usize>) -> (Vec<u8>, Vec<Vec<u8>>) {
+    match chunk_size {
+        Some(size) if blob.len() > size => {
+            let chunks: Vec<Vec<u8>> = blob.chunks(size).map(|chunk| chunk.to_vec()).collect();
+            let mut manifest = vec![CHUNKED];
+            manifest.extend(chunk_manifest(chunks.len(), &blob).into_bytes());
+            (manifest, chunks)
+        }
+        _ => (blob, Vec::new()),
+    }
+}
+
+// Reassembles a blob read by BLOB_READ_SCRIPT, checking its chunks against the manifest
+fn join_chunks(redis_key: &str, mut parts: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    let stored = parts.remove(0);
+    if stored.first() != Some(&CHUNKED) {
+        return Ok(stored);
+    }
+
+    let blob = parts.concat();
+    if stored[1..] != *chunk_manifest(parts.len(), &blob).as_bytes() {
+        return Err(CacheError::IntegrityError(format!("Chunks of {} are missing or corrupted", redis_key)));
+    }
+    Ok(blob)
+}
+
+// Key of the chunk `index` of the blob stored at `redis_key`, the scripts build the same keys
+fn chunk_key(redis_key: &str, index: usize) -> String {
+    format!("{{{}}}\0chunk\0{}", redis_key, index)
 }
 
-fn redis_hash_set_multiple<F: redis::ToRedisArgs, V: redis::ToRedisArgs>(con: &redis::Connection, key: String, v: &[(F, V)]) -> Result<()> {
-    con.hset_multiple::<String, F, V, ()>(key, v).map_err( |e| e.into())
+// `<chunks>:<length>:<FNV-1a checksum>` of a blob
+fn chunk_manifest(chunks: usize, blob: &[u8]) -> String {
+    let checksum = blob.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    format!("{}:{}:{:016x}", chunks, blob.len(), checksum)
 }
 
-fn redis_hash_get_all(con: &redis::Connection, key: String) -> Result<HashMap<String, String>> {
-    con.hgetall::<String, HashMap<String, String>>(key).map_err( |e| e.into())
+fn object_get<O: Cacheable>(con: &redis::Connection, redis_key: &str, values: &ValueFormat) -> Result<HashMap<String, String>> {
+    let (val,): (redis::Value,) = object_read::<O>(&mut redis::pipe(), redis_key).query(con)?;
+    stored_fields::<O>(val, redis_key, values)
 }
 
-fn redis_delete(con: &redis::Connection, key: String) -> Result<()> {
-    con.del::<String, ()>(key).map_err( |e| e.into())
+// Model name passed to FIELD_WRITE_SCRIPT when `field` is indexed
+fn index_prefix<O: Cacheable>(field: &str) -> &'static str {
+    if O::indexed_fields().contains(&field) {
+        O::model_name()
+    } else {
+        ""
+    }
+}
+
+fn redis_unwatch(con: &redis::Connection) -> Result<()> {
+    redis::cmd("UNWATCH").query::<()>(con).map_err( |e| e.into())
 }
 
 fn redis_key_exists(con: &redis::Connection, key: String) -> Result<bool> {
@@ -273,16 +1165,38 @@ impl CacheFunc for RedisCache {
         }
 
         let val = String::from_redis_value(&redis_val)?;
-        return T::from_str(val.as_ref()).map(|t| Some(t)).map_err(|_| CacheError::Other("An error occured while parsing a redis value".to_string()))
+        crate::parse_value(key, Some(field), &val).map(|t| Some(t))
+    }
+
+    fn hash_get_bytes(&self, key: &str, field: &str) -> Result<Option<Vec<u8>>> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+        connection.hget(key, field).map_err(|e| e.into())
     }
 
     fn hash_get_all<T: Cacheable + Clone + 'static>(&self, key: &str) -> Result<Option<T>> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+        let map: HashMap<String, Vec<u8>> = connection.hgetall(key)?;
+        match object_data::<T>(decode_fields(key, map, &self.values)?) {
+            Some((map, _)) => T::from_redis_obj(map).map(|t| Some(t)),
+            None => Ok(None),
+        }
+    }
+
+    fn hash_get_map<T: FromStr>(&self, key: &str) -> Result<HashMap<String, T>> {
         let connection = match self.connection_pool.get() {
             Ok(con) => con,
             Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
         };
         let map: HashMap<String, String> = connection.hgetall(key)?;
-        T::from_redis_obj(map).map(|t| Some(t))
+        map.into_iter().map(|(field, val)| {
+            crate::parse_value(key, Some(&field), &val).map(|t| (field, t))
+        }).collect()
     }
 
     fn hash_keys(&self, key: &str) -> Result<Vec<String>> {
@@ -301,8 +1215,17 @@ impl CacheFunc for RedisCache {
         connection.hlen(key).map_err(|e| e.into())
     }
 
-    fn hash_multiple_get(&self, _key: &str, _fields: &[&str]) -> Result<Vec<Option<String>>> {
-        unimplemented!()
+    fn hash_multiple_get(&self, key: &str, fields: &[&str]) -> Result<Vec<Option<String>>> {
+        // HMGET needs at least one field
+        if fields.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+        ::redis::cmd("HMGET").arg(key).arg(fields).query(&*connection).map_err(|e| e.into())
     }
 
     fn hash_multiple_set<V: ToString>(&self, key: &str, fv_pairs: &[(&str, V)]) -> Result<bool> {
@@ -322,12 +1245,20 @@ impl CacheFunc for RedisCache {
         connection.hset(key, field, value.to_string()).map_err(|e| e.into())
     }
 
+    fn hash_set_bytes<V: AsRef<[u8]>>(&self, key: &str, field: &str, value: V) -> Result<bool> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+        connection.hset(key, field, value.as_ref()).map_err(|e| e.into())
+    }
+
     fn hash_set_all<T: Cacheable + Clone + 'static>(&self, key: &str, cacheable: T) -> Result<bool> {
         let connection = match self.connection_pool.get() {
             Ok(con) => con,
             Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
         };
-        let fv_pairs = cacheable.to_redis_obj();
+        let fv_pairs = stored_values::<T>(key, object_fields(&cacheable)?, &self.values);
         connection.hset_multiple(key, &fv_pairs).map_err(|e| e.into())
     }
 
@@ -347,6 +1278,14 @@ impl CacheFunc for RedisCache {
         connection.hvals(key).map_err(|e| e.into())
     }
 
+    fn hash_values_bytes(&self, key: &str) -> Result<Vec<Vec<u8>>> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+        connection.hvals(key).map_err(|e| e.into())
+    }
+
     fn set_add<V: ToString>(&self, key: &str, members: &[V]) -> Result<bool> {
         let connection = match self.connection_pool.get() {
             Ok(con) => con,
@@ -356,6 +1295,15 @@ impl CacheFunc for RedisCache {
         connection.sadd(key, string_members).map_err(|e| e.into())
     }
 
+    fn set_add_bytes<V: AsRef<[u8]>>(&self, key: &str, members: &[V]) -> Result<bool> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+        let byte_members = members.iter().map(|m| m.as_ref().to_vec()).collect::<Vec<Vec<u8>>>();
+        connection.sadd(key, byte_members).map_err(|e| e.into())
+    }
+
     fn set_card(&self, key: &str) -> Result<u64> {
         let connection = match self.connection_pool.get() {
             Ok(con) => con,
@@ -404,6 +1352,14 @@ impl CacheFunc for RedisCache {
         connection.sismember(key, member.to_string()).map_err(|e|e.into())
     }
 
+    fn set_ismember_bytes<V: AsRef<[u8]>>(&self, key: &str, member: V) -> Result<bool> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+        connection.sismember(key, member.as_ref()).map_err(|e|e.into())
+    }
+
     fn set_members(&self, key: &str) -> Result<Vec<String>> {
         let connection = match self.connection_pool.get() {
             Ok(con) => con,
@@ -412,6 +1368,14 @@ impl CacheFunc for RedisCache {
         connection.smembers(key).map_err(|e|e.into())
     }
 
+    fn set_members_bytes(&self, key: &str) -> Result<Vec<Vec<u8>>> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+        connection.smembers(key).map_err(|e|e.into())
+    }
+
     fn set_move<V: ToString>(&self, key1: &str, key2: &str, member: V) -> Result<bool> {
         let connection = match self.connection_pool.get() {
             Ok(con) => con,
@@ -428,6 +1392,14 @@ impl CacheFunc for RedisCache {
         connection.srem(key, member.to_string()).map_err(|e|e.into())
     }
 
+    fn set_rem_bytes<V: AsRef<[u8]>>(&self, key: &str, member: V) -> Result<bool> {
+        let connection = match self.connection_pool.get() {
+            Ok(con) => con,
+            Err(e) => return Err(CacheError::ConnectionError(e.to_string())),
+        };
+        connection.srem(key, member.as_ref()).map_err(|e|e.into())
+    }
+
     fn set_union(&self, keys: &[&str]) -> Result<Vec<String>> {
         let connection = match self.connection_pool.get() {
             Ok(con) => con,
@@ -0,0 +1,57 @@
+//! Codecs for types implementing `Serialize` and `Deserialize`, available with the `serde` feature.
+//!
+//! `#[cache(serde = "json")]`, `"msgpack"` or `"bincode"` on a type using `#[derive(Cacheable)]` encodes
+//! each field with the matching codec instead of `ToString`/`FromStr`, so fields only need the serde traits.
+//! They can also be used on a single field with `#[cache(with = "mouscache::serde_codec::Json")]`.
+//!
+//! Binary formats are stored as lowercase hex, like `codec::Hex`.
+//! Values which can't be serialized, such as maps with non-string keys in JSON, fail to be written.
+
+pub use serde::Serialize;
+pub use serde::de::DeserializeOwned;
+use crate::CacheError;
+use crate::Result;
+use crate::codec::{Codec, Hex};
+
+/// JSON text, readable with redis-cli.
+pub struct Json;
+
+impl<T: Serialize + DeserializeOwned> Codec<T> for Json {
+    fn encode(value: &T) -> Result<String> {
+        serde_json::to_string(value).map_err(|e| CacheError::Other(format!("Unable to serialize value to JSON: {}", e)))
+    }
+
+    fn decode(raw: &str) -> Result<T> {
+        serde_json::from_str(raw).map_err(|e| CacheError::Other(format!("Unable to deserialize JSON value: {}", e)))
+    }
+}
+
+/// MessagePack, with struct fields stored by name so they can be added and reordered.
+pub struct MsgPack;
+
+impl<T: Serialize + DeserializeOwned> Codec<T> for MsgPack {
+    fn encode(value: &T) -> Result<String> {
+        let bytes = rmp_serde::to_vec_named(value).map_err(|e| CacheError::Other(format!("Unable to serialize value to MessagePack: {}", e)))?;
+        Hex::encode(&bytes)
+    }
+
+    fn decode(raw: &str) -> Result<T> {
+        let bytes: Vec<u8> = Hex::decode(raw)?;
+        rmp_serde::from_slice(&bytes).map_err(|e| CacheError::Other(format!("Unable to deserialize MessagePack value: {}", e)))
+    }
+}
+
+/// bincode, the most compact but tied to the exact field layout of the type.
+pub struct Bincode;
+
+impl<T: Serialize + DeserializeOwned> Codec<T> for Bincode {
+    fn encode(value: &T) -> Result<String> {
+        let bytes = bincode::serialize(value).map_err(|e| CacheError::Other(format!("Unable to serialize value to bincode: {}", e)))?;
+        Hex::encode(&bytes)
+    }
+
+    fn decode(raw: &str) -> Result<T> {
+        let bytes: Vec<u8> = Hex::decode(raw)?;
+        bincode::deserialize(&bytes).map_err(|e| CacheError::Other(format!("Unable to deserialize bincode value: {}", e)))
+    }
+}
@@ -1,73 +1,231 @@
 use syn;
-use syn::{Attribute};
-use syn::Meta::{List, NameValue, Word};
-use syn::NestedMeta::{Literal, Meta};
+use syn::{Attribute, Error};
+use syn::Meta::{List, NameValue, Path};
+use syn::NestedMeta::{Lit, Meta};
 use std::str::FromStr;
+use proc_macro2::TokenStream;
 
 pub struct DataAttribute {
     pub expires: Option<usize>,
+    /// Field holding the time to live of each instance
+    pub expires_field: Option<syn::Ident>,
+    /// Path of a `fn(&Self) -> Option<usize>` computing the time to live of each instance
+    pub expires_fn: Option<syn::Path>,
     pub rename: Option<String>,
+    /// Schema version of the cached field layout
+    pub version: Option<u32>,
+    /// Path of a `fn(u32, HashMap<String, String>) -> Option<HashMap<String, String>>` upgrading older entries
+    pub migrate: Option<syn::Path>,
+    /// Stores the whole object as a single encoded string, see `mouscache::Storage`
+    pub blob: bool,
+    /// `mouscache::serde_codec` codec used by fields without their own `with`
+    pub serde: Option<syn::Path>,
 }
 
-#[allow(dead_code)]
 pub struct FieldAttribute {
     pub skip: bool,
     pub rename: Option<String>,
     pub key: bool,
+    /// Maintains a `model:field:value` index of the cached keys, queried with `find_by`
+    pub index: bool,
+    pub flatten: bool,
+    pub nested: bool,
+    /// Used for skipped fields, and when the field is missing from the cached entry
+    pub default: Option<TokenStream>,
+    /// Path providing `encode(&T) -> String` and `decode(&str) -> Result<T>`
+    pub with: Option<syn::Path>,
 }
 
-pub fn value_from_lit<T: FromStr>(lit: &syn::Lit, attr_name: &str) -> Result<T, String> {
-    if let &syn::Lit::Str(ref lit) = lit {
-        let value = T::from_str(&lit.value()).map_err(move |_| { format!("Unable to parse attribute value for {}", attr_name) });
-        return value;
+fn lit_str<'a>(lit: &'a syn::Lit, attr_name: &str) -> syn::Result<&'a syn::LitStr> {
+    if let syn::Lit::Str(ref lit) = *lit {
+        Ok(lit)
     } else {
-        Err("Unable to parse attribute value".to_string())
+        Err(Error::new_spanned(lit, format!("Expected a string value for {}, like {} = \"...\"", attr_name, attr_name)))
     }
 }
 
-pub fn get_meta_items(attr: &syn::Attribute) -> Option<Vec<syn::NestedMeta>> {
-    if attr.path.segments.len() == 1 && attr.path.segments[0].ident == "cache" {
-        match attr.interpret_meta() {
-            Some(List(ref meta)) => Some(meta.nested.iter().cloned().collect()),
-            _ => {
-                // TODO: produce an error
-                None
-            }
+pub fn value_from_lit<T: FromStr>(lit: &syn::Lit, attr_name: &str) -> syn::Result<T> {
+    if let syn::Lit::Int(ref lit) = *lit {
+        return T::from_str(lit.base10_digits()).map_err(|_| Error::new_spanned(lit, format!("Unable to parse attribute value for {}", attr_name)));
+    }
+
+    let lit = lit_str(lit, attr_name)?;
+    T::from_str(&lit.value()).map_err(|_| Error::new_spanned(lit, format!("Unable to parse attribute value for {}", attr_name)))
+}
+
+/// The items of every `#[cache(...)]` attribute
+pub fn get_meta_items(attrs: &[Attribute]) -> syn::Result<Vec<syn::NestedMeta>> {
+    let mut items = Vec::new();
+    for attr in attrs.iter().filter(|a| a.path.is_ident("cache")) {
+        match attr.parse_meta()? {
+            List(meta) => items.extend(meta.nested.into_iter()),
+            meta => return Err(Error::new_spanned(meta, "Expected a list of mouscache attributes, like #[cache(...)]")),
         }
-    } else {
-        None
     }
+    Ok(items)
 }
 
-pub fn validate_data_attributes(attrs: &Vec<Attribute>) -> Result<DataAttribute, String> {
+fn attribute_name(meta: &syn::NestedMeta) -> String {
+    let path = match *meta {
+        Meta(Path(ref p)) => p,
+        Meta(NameValue(ref m)) => &m.path,
+        Meta(List(ref m)) => &m.path,
+        Lit(_) => return String::new(),
+    };
+    path.get_ident().map(|i| i.to_string()).unwrap_or_default()
+}
+
+pub fn validate_data_attributes(attrs: &[Attribute]) -> syn::Result<DataAttribute> {
     let mut expires: Option<usize> = None;
+    let mut expires_field: Option<syn::Ident> = None;
+    let mut expires_fn: Option<syn::Path> = None;
     let mut rename: Option<String> = None;
-    for meta_items in attrs.iter().filter_map(get_meta_items) {
-        for meta in meta_items {
-            match meta {
-                Meta(NameValue(ref m)) if m.ident == "expires" => {
-                    let expiration_time: usize = value_from_lit(&m.lit, "expires")?;
-                    expires = Some(expiration_time);
-                }
-                Meta(NameValue(ref m)) if m.ident == "rename" => {
-                    let name: String = value_from_lit(&m.lit, "rename")?;
-                    rename = Some(name);
-                }
-                Meta(List(ref _m)) => return Err("There is no list attribute you can use on data types with mouscache".to_string()),
-                Meta(Word(_name)) => return Err("There is no word attribute you can use on data types with mouscache".to_string()),
-                Literal(_) => return Err("There is no litteral attribute you can use on data types with mouscache".to_string()),
-                _ => return Err("Invalid mouscache attributes".to_string()),
+    let mut version: Option<u32> = None;
+    let mut migrate: Option<syn::Path> = None;
+    let mut blob = false;
+    let mut serde: Option<syn::Path> = None;
+    // expires, expires_field and expires_fn are mutually exclusive
+    let mut expiration: Option<syn::NestedMeta> = None;
+    for meta in get_meta_items(attrs)? {
+        let is_expiration = attribute_name(&meta).starts_with("expires");
+        if is_expiration && expiration.is_some() {
+            return Err(Error::new_spanned(&meta, "Only one of expires, expires_field and expires_fn can be used"));
+        }
+
+        match meta {
+            Meta(NameValue(ref m)) if m.path.is_ident("expires") => {
+                let expiration_time: usize = value_from_lit(&m.lit, "expires")?;
+                expires = Some(expiration_time);
+            }
+            Meta(NameValue(ref m)) if m.path.is_ident("expires_field") => {
+                expires_field = Some(lit_str(&m.lit, "expires_field")?.parse::<syn::Ident>()?);
+            }
+            Meta(NameValue(ref m)) if m.path.is_ident("expires_fn") => {
+                expires_fn = Some(lit_str(&m.lit, "expires_fn")?.parse::<syn::Path>()?);
+            }
+            Meta(NameValue(ref m)) if m.path.is_ident("rename") => {
+                let name: String = value_from_lit(&m.lit, "rename")?;
+                rename = Some(name);
+            }
+            Meta(NameValue(ref m)) if m.path.is_ident("version") => {
+                let schema_version: u32 = value_from_lit(&m.lit, "version")?;
+                version = Some(schema_version);
+            }
+            Meta(NameValue(ref m)) if m.path.is_ident("migrate") => {
+                migrate = Some(lit_str(&m.lit, "migrate")?.parse::<syn::Path>()?);
             }
+            Meta(NameValue(ref m)) if m.path.is_ident("storage") => {
+                let storage = lit_str(&m.lit, "storage")?;
+                blob = match storage.value().as_str() {
+                    "hash" => false,
+                    "blob" => true,
+                    other => return Err(Error::new_spanned(storage, format!("Unknown storage `{}`, expected \"hash\" or \"blob\"", other))),
+                };
+            }
+            Meta(NameValue(ref m)) if m.path.is_ident("serde") => {
+                let format = lit_str(&m.lit, "serde")?;
+                serde = Some(match format.value().as_str() {
+                    "json" => syn::parse_quote!(::mouscache::serde_codec::Json),
+                    "msgpack" => syn::parse_quote!(::mouscache::serde_codec::MsgPack),
+                    "bincode" => syn::parse_quote!(::mouscache::serde_codec::Bincode),
+                    other => return Err(Error::new_spanned(format, format!("Unknown serde format `{}`, expected \"json\", \"msgpack\" or \"bincode\"", other))),
+                });
+            }
+            Lit(ref lit) => return Err(Error::new_spanned(lit, "There is no litteral attribute you can use on data types with mouscache")),
+            ref meta => return Err(Error::new_spanned(meta, format!("Unknown mouscache data type attribute `{}`", attribute_name(meta)))),
+        }
+
+        if is_expiration {
+            expiration = Some(meta);
         }
     }
 
+    if let (None, Some(ref function)) = (version, &migrate) {
+        return Err(Error::new_spanned(function, "migrate requires a schema version, like #[cache(version = 2)]"));
+    }
+
     Ok(DataAttribute {
         expires,
+        expires_field,
+        expires_fn,
         rename,
+        version,
+        migrate,
+        blob,
+        serde,
     })
 }
 
-#[allow(dead_code)]
-pub fn validate_fields_attributes(_attrs: Vec<Attribute>) -> Result<FieldAttribute, String> {
-    Err("".to_string())
-}
\ No newline at end of file
+pub fn validate_fields_attributes(attrs: &[Attribute]) -> syn::Result<FieldAttribute> {
+    let mut skip = false;
+    let mut key = false;
+    let mut index: Option<syn::NestedMeta> = None;
+    let mut rename: Option<String> = None;
+    let mut flatten = false;
+    let mut nested = false;
+    let mut default: Option<TokenStream> = None;
+    let mut with: Option<syn::Path> = None;
+    // The last attribute a skipped field can't use
+    let mut stored: Option<syn::NestedMeta> = None;
+    for meta in get_meta_items(attrs)? {
+        match meta {
+            Meta(Path(ref p)) if p.is_ident("skip") => skip = true,
+            Meta(Path(ref p)) if p.is_ident("key") => key = true,
+            Meta(Path(ref p)) if p.is_ident("index") => index = Some(meta.clone()),
+            Meta(Path(ref p)) if p.is_ident("flatten") || p.is_ident("nested") => {
+                if flatten || nested {
+                    return Err(Error::new_spanned(&meta, "A field can't be both flattened and nested"));
+                }
+                if with.is_some() {
+                    return Err(Error::new_spanned(&meta, "A field using a codec can't be flattened or nested"));
+                }
+                if p.is_ident("flatten") {
+                    flatten = true;
+                } else {
+                    nested = true;
+                }
+                stored = Some(meta.clone());
+            }
+            Meta(Path(ref p)) if p.is_ident("default") => default = Some(quote!(::std::default::Default::default())),
+            Meta(NameValue(ref m)) if m.path.is_ident("rename") => {
+                let name: String = value_from_lit(&m.lit, "rename")?;
+                rename = Some(name);
+                stored = Some(meta.clone());
+            }
+            Meta(NameValue(ref m)) if m.path.is_ident("default") => {
+                default = Some(lit_str(&m.lit, "default")?.parse::<TokenStream>()?);
+            }
+            Meta(NameValue(ref m)) if m.path.is_ident("with") => {
+                if flatten || nested {
+                    return Err(Error::new_spanned(&meta, "A field using a codec can't be flattened or nested"));
+                }
+                with = Some(lit_str(&m.lit, "with")?.parse::<syn::Path>()?);
+                stored = Some(meta.clone());
+            }
+            Lit(ref lit) => return Err(Error::new_spanned(lit, "There is no litteral attribute you can use on fields with mouscache")),
+            ref meta => return Err(Error::new_spanned(meta, format!("Unknown mouscache field attribute `{}`", attribute_name(meta)))),
+        }
+    }
+
+    if let (true, Some(meta)) = (skip, stored) {
+        return Err(Error::new_spanned(meta, "A skipped field can't be flattened, nested, renamed or use a codec"));
+    }
+
+    match index {
+        Some(ref meta) if skip || flatten || nested => {
+            return Err(Error::new_spanned(meta, "An indexed field can't be skipped, flattened or nested"));
+        }
+        _ => {}
+    }
+
+    Ok(FieldAttribute {
+        skip,
+        rename,
+        key,
+        index: index.is_some(),
+        flatten,
+        nested,
+        default,
+        with,
+    })
+}
@@ -1,19 +1,22 @@
 use syn;
-use syn::{Ident, DeriveInput};
-use quote::Tokens;
-use proc_macro;
-use proc_macro2::Span;
+use syn::{Ident, DeriveInput, Error};
+use syn::spanned::Spanned;
+use proc_macro2::{Span, TokenStream};
 
 use crate::attr::*;
 
-pub fn impl_cacheable(input: proc_macro::TokenStream) -> Result<Tokens, String> {
-    let input: DeriveInput = syn::parse(input).unwrap();
-
+pub fn impl_cacheable(input: &DeriveInput) -> syn::Result<TokenStream> {
     let name: &Ident = &input.ident;
 
     let usages = expand_usages();
 
-    let impl_block = expand_cacheable_impl_block(&input)?;
+    let generics = bounded_generics(input)?;
+
+    let impl_block = expand_cacheable_impl_block(input, &generics)?;
+
+    let accessors = expand_field_accessors(input, &generics)?;
+
+    let cache_key = expand_cache_key(input, &generics)?;
 
     let dummy_const = Ident::new(&format!("_IMPL_DESERIALIZE_FOR_{}", name), Span::call_site());
 
@@ -23,66 +26,252 @@ pub fn impl_cacheable(input: proc_macro::TokenStream) -> Result<Tokens, String>
             #usages
 
             #impl_block
+
+            #accessors
+
+            #cache_key
         };
     })
 }
 
-fn expand_usages() -> Tokens {
+fn expand_usages() -> TokenStream {
     quote! {
         use std::any::Any;
         use std::string::ToString;
         use std::collections::hash_map::HashMap;
         use ::mouscache::CacheError;
         use ::mouscache::Result;
+        use ::mouscache::codec::Codec;
+        use ::mouscache::codec::{ObjectField, PlainField};
+    }
+}
+
+fn type_params(generics: &syn::Generics) -> Vec<Ident> {
+    generics.params.iter().filter_map(|param| match *param {
+        syn::GenericParam::Type(ref t) => Some(t.ident.clone()),
+        _ => None,
+    }).collect()
+}
+
+fn all_fields(input: &DeriveInput) -> Vec<&syn::Field> {
+    match input.data {
+        syn::Data::Struct(ref data) => data.fields.iter().collect(),
+        syn::Data::Enum(ref data) => data.variants.iter().flat_map(|v| v.fields.iter()).collect(),
+        syn::Data::Union(_) => Vec::new(),
+    }
+}
+
+fn mentions_any(ty: &syn::Type, idents: &[Ident]) -> bool {
+    let ty_tokens = quote!(#ty).to_string();
+    ty_tokens.split(|c: char| !(c.is_alphanumeric() || c == '_')).any(|t| idents.iter().any(|i| i == t))
+}
+
+/// The input generics, with the bounds the generated code needs on each field type using a type parameter
+fn bounded_generics(input: &DeriveInput) -> syn::Result<syn::Generics> {
+    let mut generics = input.generics.clone();
+    let params = type_params(&generics);
+    if params.is_empty() {
+        return Ok(generics);
     }
+
+    let mut bounds: Vec<TokenStream> = params.iter().map(|p| quote!(#p: 'static)).collect();
+
+    let serde = validate_data_attributes(&input.attrs)?.serde;
+
+    for f in all_fields(input) {
+        let attrs = validate_fields_attributes(&f.attrs)?;
+        let ty = option_inner_type(&f.ty).unwrap_or(&f.ty);
+
+        let bounded: Vec<(&syn::Type, TokenStream)> = if serde_codec(&attrs, serde.as_ref()).is_some() {
+            vec![(ty, quote!(::mouscache::serde_codec::Serialize + ::mouscache::serde_codec::DeserializeOwned))]
+        } else if attrs.skip {
+            match attrs.default {
+                Some(_) => Vec::new(),
+                None => vec![(&f.ty, quote!(::std::default::Default))],
+            }
+        } else if attrs.with.is_some() {
+            Vec::new()
+        } else if attrs.flatten {
+            vec![(ty, quote!(::mouscache::Cacheable))]
+        } else {
+            match value_kind(ty, &attrs) {
+                ValueKind::Plain => vec![(ty, quote!(::std::string::ToString + ::std::str::FromStr))],
+                ValueKind::Seq(item) => vec![(item, quote!(::std::string::ToString + ::std::str::FromStr))],
+                ValueKind::Map(k, v) => vec![
+                    (k, quote!(::std::string::ToString + ::std::str::FromStr)),
+                    (v, quote!(::std::string::ToString + ::std::str::FromStr)),
+                ],
+                ValueKind::Object => vec![(ty, quote!(::mouscache::Cacheable))],
+            }
+        };
+
+        for (t, bound) in bounded {
+            if mentions_any(t, &params) {
+                bounds.push(quote!(#t: #bound));
+            }
+        }
+    }
+
+    let where_clause = generics.make_where_clause();
+    for bound in bounds {
+        where_clause.predicates.push(syn::parse2::<syn::WherePredicate>(bound)?);
+    }
+
+    Ok(generics)
 }
 
-fn expand_cacheable_impl_block(input: &DeriveInput) -> Result<Tokens, String> {
+fn expand_cacheable_impl_block(input: &DeriveInput, generics: &syn::Generics) -> syn::Result<TokenStream> {
     let ident: &Ident = &input.ident;
 
     let data_attrs = validate_data_attributes(&input.attrs)?;
 
-    let base_func = expand_base_function(ident, data_attrs);
+    let expires_after_func = expand_expires_after(input, &data_attrs)?;
 
-    let redis_func = expand_redis_function(input)?;
+    let schema_func = expand_schema_functions(&data_attrs);
+
+    let index_func = expand_indexed_fields(input, &data_attrs)?;
+
+    let redis_func = expand_redis_function(input, data_attrs.serde.as_ref())?;
+
+    let base_func = expand_base_function(ident, &type_params(generics), data_attrs, expires_after_func);
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     Ok(quote! {
-        impl ::mouscache::Cacheable for #ident {
+        impl #impl_generics ::mouscache::Cacheable for #ident #ty_generics #where_clause {
             #base_func
 
+            #schema_func
+
+            #index_func
+
             #redis_func
         }
     })
 }
 
-fn  expand_base_function(ident: &Ident, data_attrs: DataAttribute) -> Tokens {
-    let ident = if let Some(name) = data_attrs.rename {
-        Ident::new(name.as_str(), ident.span())
+fn expand_expires_after(input: &DeriveInput, data_attrs: &DataAttribute) -> syn::Result<TokenStream> {
+    let ttl = if let Some(ttl) = data_attrs.expires {
+        quote!(Option::from(#ttl))
+    } else if let Some(ref field) = data_attrs.expires_field {
+        let named_field = match input.data {
+            syn::Data::Struct(syn::DataStruct { fields: syn::Fields::Named(ref f_named), .. }) => {
+                f_named.named.iter().find(|f| f.ident.as_ref() == Some(field))
+            }
+            _ => None,
+        };
+
+        // Integer fields, or `Option` of integers for instances which don't expire. Negative values don't expire
+        // either, while 0 expires right away
+        match named_field {
+            Some(f) if option_inner_type(&f.ty).is_some() => {
+                quote!(self.#field.and_then(|ttl| <usize as ::std::convert::TryFrom<_>>::try_from(ttl).ok()))
+            }
+            Some(_) => quote!(<usize as ::std::convert::TryFrom<_>>::try_from(self.#field).ok()),
+            None => return Err(Error::new_spanned(field, format!("expires_field: no field named `{}`", field))),
+        }
+    } else if let Some(ref function) = data_attrs.expires_fn {
+        quote!(#function(self))
     } else {
-        ident.clone()
+        quote!(None)
     };
 
-    let expires_after_func = if let Some(ttl) = data_attrs.expires {
+    Ok(quote! {
+        fn expires_after(&self) -> Option<usize> {
+            #ttl
+        }
+    })
+}
+
+fn expand_schema_functions(data_attrs: &DataAttribute) -> TokenStream {
+    let storage = if data_attrs.blob {
         quote! {
-            fn expires_after(&self) -> Option<usize> {
-                Option::from(#ttl)
+            fn storage() -> ::mouscache::Storage where Self: Sized {
+                ::mouscache::Storage::Blob
             }
         }
     } else {
-        quote! {
-            fn expires_after(&self) -> Option<usize> {
-                None
+        quote!()
+    };
+
+    let version = match data_attrs.version {
+        Some(version) => version,
+        None => return storage,
+    };
+
+    let migrate = data_attrs.migrate.as_ref().map(|function| quote! {
+        fn migrate(from: u32, obj: HashMap<String, String>) -> Option<HashMap<String, String>> where Self: Sized {
+            #function(from, obj)
+        }
+    });
+
+    quote! {
+        #storage
+
+        fn schema_version() -> u32 where Self: Sized {
+            #version
+        }
+
+        #migrate
+    }
+}
+
+fn expand_indexed_fields(input: &DeriveInput, data_attrs: &DataAttribute) -> syn::Result<TokenStream> {
+    let mut names: Vec<String> = Vec::new();
+    match input.data {
+        syn::Data::Struct(ref data) => {
+            for (i, f) in data.fields.iter().enumerate() {
+                let attrs = validate_fields_attributes(&f.attrs)?;
+                if attrs.index && data_attrs.blob {
+                    return Err(Error::new_spanned(f, "#[cache(index)] can't be used with blob storage"));
+                }
+                if attrs.index {
+                    let default_name = f.ident.as_ref().map(|ident| ident.to_string()).unwrap_or_else(|| i.to_string());
+                    names.push(field_name(default_name, &attrs));
+                }
             }
         }
+        _ => {
+            for f in all_fields(input) {
+                if validate_fields_attributes(&f.attrs)?.index {
+                    return Err(Error::new_spanned(f, "#[cache(index)] can only be used on struct fields"));
+                }
+            }
+        }
+    }
+
+    if names.is_empty() {
+        return Ok(quote!());
+    }
+
+    Ok(quote! {
+        fn indexed_fields() -> &'static [&'static str] where Self: Sized {
+            &[#(#names),*]
+        }
+    })
+}
+
+fn  expand_base_function(ident: &Ident, type_params: &[Ident], data_attrs: DataAttribute, expires_after_func: TokenStream) -> TokenStream {
+    let name = if let Some(name) = data_attrs.rename {
+        name
+    } else {
+        ident.to_string()
+    };
+
+    // Generic types include their type arguments, so `Envelope<User>` and `Envelope<Device>` don't collide
+    let model_name = if type_params.is_empty() {
+        quote!(#name)
+    } else {
+        quote!(::mouscache::generic_model_name::<Self>(#name, &[#(::std::any::type_name::<#type_params>()),*]))
     };
 
     quote! {
         #[inline]
         fn model_name() -> &'static str where Self: Sized {
-            stringify!(#ident)
+            #model_name
         }
 
-        fn as_any(&self) -> &Any {
+        fn as_any(&self) -> &dyn Any {
             self
         }
 
@@ -90,77 +279,510 @@ fn  expand_base_function(ident: &Ident, data_attrs: DataAttribute) -> Tokens {
     }
 }
 
-fn expand_redis_function(input: &DeriveInput) -> Result<Tokens, String> {
-    let struct_ident: &Ident = &input.ident;
+/// The codec of a field encoded with `#[cache(serde = "...")]`, if it doesn't use another encoding
+fn serde_codec<'a>(attrs: &FieldAttribute, serde: Option<&'a syn::Path>) -> Option<&'a syn::Path> {
+    if attrs.skip || attrs.flatten || attrs.nested || attrs.with.is_some() {
+        None
+    } else {
+        serde
+    }
+}
 
-    let fields = match input.data {
-        syn::Data::Struct(ref data) => data.fields.clone(),
-        syn::Data::Enum(_) => return Err(String::from("#[derive(Cacheable)] only apply to structs at this time")),
-        syn::Data::Union(_) => return Err(String::from("#[derive(Cacheable)] only apply to structs at this time")),
-    };
+fn fields_with_attributes<'a, I>(fields: I, serde: Option<&syn::Path>) -> syn::Result<Vec<(syn::Field, FieldAttribute)>>
+    where I: IntoIterator<Item = &'a syn::Field> {
+    fields.into_iter().map(|f| {
+        let mut attrs = validate_fields_attributes(&f.attrs)?;
+        if attrs.flatten && option_inner_type(&f.ty).is_some() {
+            return Err(Error::new_spanned(&f.ty, "#[cache(flatten)] can't be used on Option fields, use #[cache(nested)] instead"));
+        }
+        if let Some(codec) = serde_codec(&attrs, serde) {
+            attrs.with = Some(codec.clone());
+        }
+        Ok((f.clone(), attrs))
+    }).collect()
+}
 
-    let named_fields = match fields {
-        syn::Fields::Named(ref f_named) => f_named.named.clone(),
-        syn::Fields::Unnamed(_) => return Err(String::from("Unnamed fields are not implemented at this time -> #[derive(Cacheable)] only apply to structs with named fields")),
-        syn::Fields::Unit => return Err(String::from("Unit fields are not implemented at this time -> #[derive(Cacheable)] only apply to structs with named fields")),
+/// How a field value is turned into a hash field value
+enum ValueKind<'a> {
+    /// `ToString` / `FromStr`, or the encoded map of a `Cacheable` type (see `mouscache::codec::Field`)
+    Plain,
+    /// Length-prefixed items, see `mouscache::codec`
+    Seq(&'a syn::Type),
+    Map(&'a syn::Type, &'a syn::Type),
+    /// A `Cacheable` stored as the encoded map of its fields
+    Object,
+}
+
+/// Splits `a::b::Name<T, U>` into `Name` and its type arguments
+fn type_args(ty: &syn::Type) -> Option<(String, Vec<&syn::Type>)> {
+    if let syn::Type::Path(syn::TypePath { qself: None, ref path }) = *ty {
+        if let Some(segment) = path.segments.last() {
+            let mut args = Vec::new();
+            if let syn::PathArguments::AngleBracketed(ref bracketed) = segment.arguments {
+                for arg in bracketed.args.iter() {
+                    match *arg {
+                        syn::GenericArgument::Type(ref t) => args.push(t),
+                        _ => return None,
+                    }
+                }
+            }
+            return Some((segment.ident.to_string(), args));
+        }
+    }
+    None
+}
+
+fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    match type_args(ty) {
+        Some((ref name, ref args)) if name == "Option" && args.len() == 1 => Some(args[0]),
+        _ => None,
+    }
+}
+
+fn value_kind<'a>(ty: &'a syn::Type, attrs: &FieldAttribute) -> ValueKind<'a> {
+    if attrs.nested {
+        return ValueKind::Object;
+    }
+
+    if let Some((name, args)) = type_args(ty) {
+        match (name.as_str(), args.len()) {
+            ("Vec", 1) | ("VecDeque", 1) | ("HashSet", 1) | ("BTreeSet", 1) => return ValueKind::Seq(args[0]),
+            ("HashMap", 2) | ("BTreeMap", 2) => return ValueKind::Map(args[0], args[1]),
+            _ => {}
+        }
+    }
+    ValueKind::Plain
+}
+
+/// `value` must be an expression of type `&ty`, encoding errors are returned with `?`
+fn expand_value_encode(ty: &syn::Type, attrs: &FieldAttribute, value: TokenStream) -> TokenStream {
+    if let Some(ref codec) = attrs.with {
+        return quote!(::mouscache::codec::Encoded::into_result(#codec::encode(#value))?);
+    }
+
+    match value_kind(ty, attrs) {
+        ValueKind::Plain => quote_spanned!(ty.span()=> (&::mouscache::codec::Field::<#ty>(::std::marker::PhantomData)).encode_field(#value)?),
+        ValueKind::Seq(_) => quote!(::mouscache::codec::encode_seq(#value)),
+        ValueKind::Map(_, _) => quote!(::mouscache::codec::encode_map(#value)),
+        ValueKind::Object => quote!(::mouscache::codec::encode_object(#value)?),
+    }
+}
+
+fn expand_field_parse(name: &str, ty: &syn::Type, attrs: &FieldAttribute, raw: TokenStream) -> TokenStream {
+    let decoded = if let Some(ref codec) = attrs.with {
+        quote!(#codec::decode(&#raw))
+    } else {
+        match value_kind(ty, attrs) {
+            ValueKind::Plain => quote_spanned!(ty.span()=> (&::mouscache::codec::Field::<#ty>(::std::marker::PhantomData)).decode_field(&#raw)),
+            ValueKind::Seq(item) => quote!(::mouscache::codec::decode_seq::<#ty, #item>(&#raw)),
+            ValueKind::Map(k, v) => quote!(::mouscache::codec::decode_map::<#ty, #k, #v>(&#raw)),
+            ValueKind::Object => quote!(::mouscache::codec::decode_object::<#ty>(&#raw)),
+        }
     };
 
-    let mut field_tokens: Vec<Tokens> = Vec::new();
+    quote! {
+        match #decoded {
+            Ok(o) => o,
+            _ => return Err(CacheError::Other(format!("Unable to parse field {} from string into {}", #name, stringify!(#ty)))),
+        }
+    }
+}
 
-    for f in named_fields.iter() {
-        if let Some(ref ident) = f.ident {
-            field_tokens.push(quote!(String::from(stringify!(#ident)), self.#ident.to_string()))
+/// Hash field name, `#[cache(rename = "...")]` or the field name / position
+fn field_name(default_name: String, attrs: &FieldAttribute) -> String {
+    match attrs.rename {
+        Some(ref name) => name.clone(),
+        None => default_name,
+    }
+}
+
+/// An empty name flattens the nested fields as is
+fn flatten_prefix(name: &str) -> String {
+    if name.is_empty() {
+        String::new()
+    } else {
+        format!("{}.", name)
+    }
+}
+
+/// Statements pushing the hash fields of one value to `temp_vec`, `value` being an expression of type `&ty`
+fn expand_field_to_redis(name: &str, f: &syn::Field, attrs: &FieldAttribute, value: TokenStream) -> TokenStream {
+    if attrs.skip {
+        quote!()
+    } else if attrs.flatten {
+        let prefix = flatten_prefix(name);
+        quote! {
+            for (k, v) in ::mouscache::Cacheable::try_to_redis_obj(#value)? {
+                temp_vec.push((format!("{}{}", #prefix, k), v));
+            }
+        }
+    } else if let Some(inner) = option_inner_type(&f.ty) {
+        let encode = expand_value_encode(inner, attrs, quote!(value));
+        quote! {
+            if let Some(ref value) = *#value {
+                temp_vec.push((String::from(#name), #encode));
+            }
+        }
+    } else {
+        let encode = expand_value_encode(&f.ty, attrs, value);
+        quote! {
+            temp_vec.push((String::from(#name), #encode));
         }
     }
+}
 
+/// Statement binding `binding` to the value read from the `map` hash fields
+fn expand_field_from_redis(binding: &Ident, name: &str, f: &syn::Field, attrs: &FieldAttribute) -> TokenStream {
     let hmap_ident = Ident::new("map", Span::call_site());
 
-    let mut field_deser_tokens: Vec<Tokens> = Vec::new();
+    let default = match attrs.default {
+        Some(ref expr) => Some(quote!(#expr)),
+        None if attrs.skip => Some(quote!(::std::default::Default::default())),
+        None => None,
+    };
+
+    if attrs.skip {
+        return quote! {
+            let #binding = #default;
+        };
+    }
+
+    if attrs.flatten {
+        let ty = &f.ty;
+        let prefix = flatten_prefix(name);
+        let parse = quote! {
+            match <#ty as ::mouscache::Cacheable>::from_redis_obj(nested) {
+                Ok(o) => o,
+                Err(e) => return Err(CacheError::Other(format!("Unable to parse nested field {}: {}", #name, e))),
+            }
+        };
+        let value = match default {
+            Some(ref expr) => quote! {
+                if nested.is_empty() {
+                    #expr
+                } else {
+                    #parse
+                }
+            },
+            None => parse,
+        };
+        return quote! {
+            let #binding = {
+                let prefix = #prefix;
+                let nested = #hmap_ident.iter()
+                    .filter(|&(k, _)| k.starts_with(prefix))
+                    .map(|(k, v)| (k[prefix.len()..].to_string(), v.clone()))
+                    .collect::<HashMap<String, String>>();
+                #value
+            };
+        };
+    }
+
+    if let Some(inner) = option_inner_type(&f.ty) {
+        let parse = expand_field_parse(name, inner, attrs, quote!(obj));
+        let missing = default.unwrap_or_else(|| quote!(None));
+        return quote! {
+            let #binding = if let Some(obj) = #hmap_ident.get(#name) {
+                Some(#parse)
+            } else {
+                #missing
+            };
+        };
+    }
+
+    let parse = expand_field_parse(name, &f.ty, attrs, quote!(obj));
+    let missing = default.unwrap_or_else(|| quote! {
+        return Err(CacheError::Other(format!("Unable to parse field {}", #name)))
+    });
+    quote! {
+        let #binding = if let Some(obj) = #hmap_ident.get(#name) {
+            #parse
+        } else {
+           #missing
+        };
+    }
+}
+
+fn expand_cache_key(input: &DeriveInput, generics: &syn::Generics) -> syn::Result<TokenStream> {
+    let ident: &Ident = &input.ident;
+
+    let mut key_field: Option<TokenStream> = None;
+    match input.data {
+        syn::Data::Struct(ref data) => {
+            for (i, f) in data.fields.iter().enumerate() {
+                if validate_fields_attributes(&f.attrs)?.key {
+                    if key_field.is_some() {
+                        return Err(Error::new_spanned(f, "Only one field can be marked #[cache(key)]"));
+                    }
+                    key_field = Some(match f.ident {
+                        Some(ref field_ident) => quote!(#field_ident),
+                        None => {
+                            let index = syn::Index::from(i);
+                            quote!(#index)
+                        }
+                    });
+                }
+            }
+        }
+        _ => {
+            for f in all_fields(input) {
+                if validate_fields_attributes(&f.attrs)?.key {
+                    return Err(Error::new_spanned(f, "#[cache(key)] can only be used on struct fields"));
+                }
+            }
+        }
+    }
+
+    let key_field = match key_field {
+        Some(key_field) => key_field,
+        None => return Ok(quote!()),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::mouscache::CacheKey for #ident #ty_generics #where_clause {
+            fn cache_key(&self) -> String {
+                ToString::to_string(&self.#key_field)
+            }
+        }
+    })
+}
+
+fn expand_field_accessors(input: &DeriveInput, generics: &syn::Generics) -> syn::Result<TokenStream> {
+    let struct_ident: &Ident = &input.ident;
+
+    // Only named fields can be accessed by name, enum variants don't share a field set, and blobs are read whole
+    let named_fields = match input.data {
+        syn::Data::Struct(syn::DataStruct { fields: syn::Fields::Named(ref f_named), .. }) => {
+            let data_attrs = validate_data_attributes(&input.attrs)?;
+            if data_attrs.blob {
+                return Ok(quote!());
+            }
+            fields_with_attributes(f_named.named.iter(), data_attrs.serde.as_ref())?
+        }
+        _ => return Ok(quote!()),
+    };
+
+    let mut accessor_tokens: Vec<TokenStream> = Vec::new();
+
+    for &(ref f, ref attrs) in named_fields.iter() {
+        // A flattened value spans several hash fields
+        if attrs.flatten || attrs.skip {
+            continue;
+        }
 
-    for f in named_fields.iter() {
-        let t = f.ty.clone();
         if let Some(ref ident) = f.ident {
-            field_deser_tokens.push(quote! {
-                let #ident = if let Some(obj) = #hmap_ident.get(&stringify!(#ident).to_string()) {
-                    match obj.parse::<#t>() {
-                        Ok(o) => o,
-                        _ => return Err(CacheError::Other(format!("Unable to parse field {} from string into {}", stringify!(#ident), stringify!(#(f.ty))))),
+            let name = field_name(ident.to_string(), attrs);
+            let getter = Ident::new(&format!("get_{}", ident), Span::call_site());
+            let setter = Ident::new(&format!("set_{}", ident), Span::call_site());
+
+            // Optional fields read as None when absent and are removed when set to None
+            let (t, set_value) = if let Some(inner) = option_inner_type(&f.ty) {
+                let encode = expand_value_encode(inner, attrs, quote!(&v));
+                (inner, quote! {
+                    match value {
+                        Some(v) => cache.set_field::<K, Self, String>(key, #name, #encode),
+                        None => cache.delete_field::<K, Self>(key, #name),
                     }
-                } else {
-                   return Err(CacheError::Other(format!("Unable to parse field {}", stringify!(#ident))));
-                };
+                })
+            } else {
+                let encode = expand_value_encode(&f.ty, attrs, quote!(&value));
+                (&f.ty, quote! {
+                    cache.set_field::<K, Self, String>(key, #name, #encode)
+                })
+            };
+            let value_ty = &f.ty;
+            let parse = expand_field_parse(&name, t, attrs, quote!(raw));
+
+            accessor_tokens.push(quote! {
+                pub fn #getter<K: ToString>(cache: &::mouscache::Cache, key: K) -> Result<Option<#t>> {
+                    if let Some(raw) = cache.get_field::<K, Self>(key, #name)? {
+                        return Ok(Some(#parse));
+                    }
+                    Ok(None)
+                }
+
+                pub fn #setter<K: ToString>(cache: &::mouscache::Cache, key: K, value: #value_ty) -> Result<bool> {
+                    #set_value
+                }
             });
         }
     }
 
-    let mut struct_ident_vec: Vec<Tokens> = Vec::new();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    for f in named_fields.iter() {
-        if let Some(ref ident) = f.ident {
-            struct_ident_vec.push(quote!(#ident,));
+    Ok(quote! {
+        #[allow(dead_code)]
+        impl #impl_generics #struct_ident #ty_generics #where_clause {
+            #(#accessor_tokens)*
         }
+    })
+}
+
+fn expand_redis_function(input: &DeriveInput, serde: Option<&syn::Path>) -> syn::Result<TokenStream> {
+    match input.data {
+        syn::Data::Struct(ref data) => expand_struct_redis_function(&input.ident, data, serde),
+        syn::Data::Enum(ref data) => expand_enum_redis_function(&input.ident, data, serde),
+        syn::Data::Union(ref data) => Err(Error::new_spanned(data.union_token, "#[derive(Cacheable)] only apply to structs and enums at this time")),
     }
+}
+
+/// Generated code reading and writing one set of fields, shared by structs and enum variants
+struct FieldsCodec {
+    /// Pattern binding every field by reference
+    pattern: TokenStream,
+    /// Expression building the value back from the bindings
+    construct: TokenStream,
+    to_redis: Vec<TokenStream>,
+    from_redis: Vec<TokenStream>,
+}
 
-    let return_token = quote! {
-        return Ok(#struct_ident {
-            #(#struct_ident_vec)*
+/// Positional fields are named `0`, `1`, ... except a flattened newtype field, which is stored as is
+fn expand_fields_codec(path: TokenStream, fields: &syn::Fields, serde: Option<&syn::Path>) -> syn::Result<FieldsCodec> {
+    let fields_attrs = match *fields {
+        syn::Fields::Named(ref f_named) => fields_with_attributes(f_named.named.iter(), serde)?,
+        syn::Fields::Unnamed(ref f_unnamed) => fields_with_attributes(f_unnamed.unnamed.iter(), serde)?,
+        syn::Fields::Unit => Vec::new(),
+    };
+
+    let newtype = fields_attrs.len() == 1 && fields_attrs[0].0.ident.is_none();
+
+    let mut bindings: Vec<Ident> = Vec::new();
+    let mut ref_bindings: Vec<TokenStream> = Vec::new();
+    let mut to_redis: Vec<TokenStream> = Vec::new();
+    let mut from_redis: Vec<TokenStream> = Vec::new();
+
+    for (i, &(ref f, ref attrs)) in fields_attrs.iter().enumerate() {
+        let (binding, name) = match f.ident {
+            Some(ref ident) => (ident.clone(), field_name(ident.to_string(), attrs)),
+            None if newtype && attrs.flatten && attrs.rename.is_none() => (Ident::new("__field0", Span::call_site()), String::new()),
+            None => (Ident::new(&format!("__field{}", i), Span::call_site()), field_name(i.to_string(), attrs)),
+        };
+        to_redis.push(expand_field_to_redis(&name, f, attrs, quote!(#binding)));
+        from_redis.push(expand_field_from_redis(&binding, &name, f, attrs));
+        ref_bindings.push(match (attrs.skip, f.ident.is_some()) {
+            (true, true) => quote!(#binding: _),
+            (true, false) => quote!(_),
+            (false, _) => quote!(ref #binding),
         });
+        bindings.push(binding);
+    }
+
+    let field_bindings = bindings.iter();
+
+    let (pattern, construct) = match *fields {
+        syn::Fields::Named(_) => (quote!(#path { #(#ref_bindings),* }), quote!(#path { #(#field_bindings),* })),
+        syn::Fields::Unnamed(_) => (quote!(#path(#(#ref_bindings),*)), quote!(#path(#(#field_bindings),*))),
+        syn::Fields::Unit => (quote!(#path), quote!(#path)),
     };
 
-    Ok(quote! {
+    Ok(FieldsCodec {
+        pattern,
+        construct,
+        to_redis,
+        from_redis,
+    })
+}
+
+/// `to_redis_obj` can't report encoding errors, the caches go through `try_to_redis_obj` instead
+fn expand_to_redis_obj(ident: &Ident) -> TokenStream {
+    quote! {
         fn to_redis_obj(&self) -> Vec<(String, String)> {
+            match ::mouscache::Cacheable::try_to_redis_obj(self) {
+                Ok(fields) => fields,
+                Err(e) => panic!("Unable to encode {}: {}", stringify!(#ident), e),
+            }
+        }
+    }
+}
+
+/// Unit structs are stored as a marker hash, since an empty hash can't be stored in redis
+fn expand_struct_redis_function(struct_ident: &Ident, data: &syn::DataStruct, serde: Option<&syn::Path>) -> syn::Result<TokenStream> {
+    let hmap_ident = Ident::new("map", Span::call_site());
+
+    let FieldsCodec { pattern, construct, to_redis, from_redis } = expand_fields_codec(quote!(#struct_ident), &data.fields, serde)?;
+
+    let marker = match data.fields {
+        syn::Fields::Unit => quote! {
+            temp_vec.push((String::from(::mouscache::UNIT_FIELD), String::new()));
+        },
+        _ => quote!(),
+    };
+
+    let to_redis_obj = expand_to_redis_obj(struct_ident);
+
+    Ok(quote! {
+        #to_redis_obj
+
+        fn try_to_redis_obj(&self) -> Result<Vec<(String, String)>> {
             let mut temp_vec = Vec::new();
-            #(temp_vec.push((#field_tokens));)*
-            temp_vec
+            let #pattern = *self;
+            #marker
+            #(#to_redis)*
+            Ok(temp_vec)
         }
 
         fn from_redis_obj(#hmap_ident: HashMap<String, String>) -> Result<Self> where Self: Sized {
-            if #hmap_ident.len() > 0 {
-                #(#field_deser_tokens)*
+            #(#from_redis)*
+
+            return Ok(#construct);
+        }
+    })
+}
 
-                #return_token
+/// Enums store the variant name in `mouscache::VARIANT_FIELD`, next to the variant fields.
+fn expand_enum_redis_function(enum_ident: &Ident, data: &syn::DataEnum, serde: Option<&syn::Path>) -> syn::Result<TokenStream> {
+    let hmap_ident = Ident::new("map", Span::call_site());
+
+    let mut ser_arms: Vec<TokenStream> = Vec::new();
+    let mut deser_arms: Vec<TokenStream> = Vec::new();
+
+    for variant in data.variants.iter() {
+        let variant_ident = &variant.ident;
+        let variant_name = variant_ident.to_string();
+
+        let FieldsCodec { pattern, construct, to_redis, from_redis } = expand_fields_codec(quote!(#enum_ident::#variant_ident), &variant.fields, serde)?;
+
+        ser_arms.push(quote! {
+            #pattern => {
+                temp_vec.push((String::from(::mouscache::VARIANT_FIELD), String::from(#variant_name)));
+                #(#to_redis)*
+            }
+        });
+
+        deser_arms.push(quote! {
+            #variant_name => {
+                #(#from_redis)*
+
+                Ok(#construct)
+            }
+        });
+    }
+
+    let to_redis_obj = expand_to_redis_obj(enum_ident);
+
+    Ok(quote! {
+        #to_redis_obj
+
+        fn try_to_redis_obj(&self) -> Result<Vec<(String, String)>> {
+            let mut temp_vec = Vec::new();
+            match *self {
+                #(#ser_arms)*
+            }
+            Ok(temp_vec)
+        }
+
+        fn from_redis_obj(#hmap_ident: HashMap<String, String>) -> Result<Self> where Self: Sized {
+            let variant = match #hmap_ident.get(::mouscache::VARIANT_FIELD) {
+                Some(v) => v.clone(),
+                None => return Err(CacheError::Other(format!("Missing variant field for {}", stringify!(#enum_ident)))),
+            };
+
+            match variant.as_str() {
+                #(#deser_arms)*
+                _ => Err(CacheError::Other(format!("Unknown variant {} for {}", variant, stringify!(#enum_ident)))),
             }
-            return Err(CacheError::Other(String::new()));
         }
     })
-}
\ No newline at end of file
+}
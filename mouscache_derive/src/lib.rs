@@ -1,6 +1,7 @@
 //! This crate provides Mouscache's derive macros.
 //!
 //! ```rust
+//! extern crate mouscache;
 //! #[macro_use]
 //! extern crate mouscache_derive;
 //!
@@ -27,8 +28,9 @@ mod derive;
 
 #[proc_macro_derive(Cacheable, attributes(cache))]
 pub fn derive_cacheable(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    match derive::impl_cacheable(input) {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    match derive::impl_cacheable(&input) {
         Ok(gen) => gen.into(),
-        Err(msg) => panic!(msg),
+        Err(e) => e.to_compile_error().into(),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,311 @@
+use mouscache;
+
+#[derive(Cacheable, Clone, Debug)]
+struct OneTimeToken {
+    owner: String,
+    scope: String,
+}
+
+#[test]
+fn memory_cache_take_test() {
+    let token = OneTimeToken {
+        owner: String::from("alice"),
+        scope: String::from("login"),
+    };
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("token", token.clone());
+
+    let taken: OneTimeToken = cache.take("token").unwrap().unwrap();
+    assert_eq!(token.owner, taken.owner);
+    assert_eq!(token.scope, taken.scope);
+
+    assert!(cache.take::<&str, OneTimeToken>("token").unwrap().is_none());
+    assert!(cache.get::<&str, OneTimeToken>("token").unwrap().is_none());
+}
+
+#[test]
+fn memory_cache_swap_test() {
+    let first = OneTimeToken {
+        owner: String::from("alice"),
+        scope: String::from("login"),
+    };
+
+    let second = OneTimeToken {
+        owner: String::from("bob"),
+        scope: String::from("reset"),
+    };
+
+    let cache = mouscache::memory();
+
+    assert!(cache.swap("token", first.clone()).unwrap().is_none());
+
+    let previous: OneTimeToken = cache.swap("token", second.clone()).unwrap().unwrap();
+    assert_eq!(first.owner, previous.owner);
+
+    let current: OneTimeToken = cache.get("token").unwrap().unwrap();
+    assert_eq!(second.owner, current.owner);
+    assert_eq!(second.scope, current.scope);
+}
+
+#[test]
+fn memory_cache_concurrent_take_test() {
+    use std::thread;
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("token", OneTimeToken {
+        owner: String::from("alice"),
+        scope: String::from("login"),
+    });
+
+    let mut handle_vec = Vec::new();
+
+    for _i in 0..10 {
+        let cache_clone = cache.clone();
+
+        handle_vec.push(thread::spawn(move || {
+            cache_clone.take::<&str, OneTimeToken>("token").unwrap().is_some()
+        }));
+    }
+
+    let consumed = handle_vec.into_iter().map(|h| h.join().unwrap()).filter(|taken| *taken).count();
+
+    assert_eq!(1, consumed);
+}
+
+#[test]
+fn redis_cache_take_swap_test() {
+    let first = OneTimeToken {
+        owner: String::from("alice"),
+        scope: String::from("login"),
+    };
+
+    let second = OneTimeToken {
+        owner: String::from("bob"),
+        scope: String::from("reset"),
+    };
+
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.remove::<&str, OneTimeToken>("token");
+
+    assert!(cache.swap("token", first.clone()).unwrap().is_none());
+
+    let previous: OneTimeToken = cache.swap("token", second.clone()).unwrap().unwrap();
+    assert_eq!(first.owner, previous.owner);
+
+    let taken: OneTimeToken = cache.take("token").unwrap().unwrap();
+    assert_eq!(second.owner, taken.owner);
+    assert_eq!(second.scope, taken.scope);
+
+    assert!(cache.take::<&str, OneTimeToken>("token").unwrap().is_none());
+}
+
+#[derive(Cacheable, Clone, Debug)]
+struct HitCounter {
+    hits: u32,
+}
+
+#[test]
+fn memory_cache_update_test() {
+    let cache = mouscache::memory();
+
+    let status = cache.update::<&str, HitCounter, _>("counter", |c| c.hits += 1).unwrap();
+    assert!(!status.existed);
+    assert!(cache.get::<&str, HitCounter>("counter").unwrap().is_none());
+
+    let _ = cache.insert("counter", HitCounter { hits: 0 });
+
+    let status = cache.update::<&str, HitCounter, _>("counter", |c| c.hits += 1).unwrap();
+    assert!(status.existed);
+    assert_eq!(0, status.retries);
+
+    let counter: HitCounter = cache.get("counter").unwrap().unwrap();
+    assert_eq!(1, counter.hits);
+}
+
+#[test]
+fn memory_cache_update_retries_on_concurrent_write_test() {
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("counter", HitCounter { hits: 0 });
+
+    // The closure runs outside the lock, so it can use the cache, and the write it makes wins over the first attempt
+    let mut attempts = 0;
+    let status = cache.update::<&str, HitCounter, _>("counter", |c| {
+        attempts += 1;
+        if attempts == 1 {
+            let _ = cache.insert("counter", HitCounter { hits: 10 });
+        }
+        c.hits += 1;
+    }).unwrap();
+    assert!(status.existed);
+    assert_eq!(1, status.retries);
+
+    let counter: HitCounter = cache.get("counter").unwrap().unwrap();
+    assert_eq!(11, counter.hits);
+}
+
+#[test]
+fn memory_cache_update_retries_on_reinsert_test() {
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("counter", HitCounter { hits: 0 });
+
+    let mut attempts = 0;
+    let status = cache.update::<&str, HitCounter, _>("counter", |c| {
+        attempts += 1;
+        if attempts == 1 {
+            cache.remove::<&str, HitCounter>("counter").unwrap();
+            cache.insert("counter", HitCounter { hits: 100 }).unwrap();
+        }
+        c.hits += 1;
+    }).unwrap();
+
+    assert!(status.existed);
+    assert_eq!(1, status.retries);
+
+    let counter: HitCounter = cache.get("counter").unwrap().unwrap();
+    assert_eq!(101, counter.hits);
+}
+
+#[test]
+fn memory_cache_concurrent_update_test() {
+    use std::thread;
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("counter", HitCounter { hits: 0 });
+
+    let mut handle_vec = Vec::new();
+
+    for _i in 0..10 {
+        let cache_clone = cache.clone();
+
+        handle_vec.push(thread::spawn(move || {
+            for _j in 0..100 {
+                cache_clone.update::<&str, HitCounter, _>("counter", |c| c.hits += 1).unwrap();
+            }
+        }));
+    }
+
+    for handle in handle_vec {
+        let _ = handle.join();
+    }
+
+    let counter: HitCounter = cache.get("counter").unwrap().unwrap();
+    assert_eq!(1000, counter.hits);
+}
+
+#[test]
+fn redis_cache_concurrent_update_test() {
+    use std::thread;
+
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.insert("counter", HitCounter { hits: 0 });
+
+    let mut handle_vec = Vec::new();
+
+    for _i in 0..10 {
+        let cache_clone = cache.clone();
+
+        handle_vec.push(thread::spawn(move || {
+            for _j in 0..10 {
+                let status = cache_clone.update::<&str, HitCounter, _>("counter", |c| c.hits += 1).unwrap();
+                assert!(status.existed);
+            }
+        }));
+    }
+
+    for handle in handle_vec {
+        let _ = handle.join();
+    }
+
+    let counter: HitCounter = cache.get("counter").unwrap().unwrap();
+    assert_eq!(100, counter.hits);
+}
+
+#[test]
+fn memory_cache_entry_test() {
+    use mouscache::Entry;
+
+    let cache = mouscache::memory();
+
+    let counter = cache.entry::<&str, HitCounter>("entry").unwrap()
+        .and_modify(|c| c.hits += 1)
+        .or_insert(HitCounter { hits: 0 }).unwrap();
+    assert_eq!(0, counter.hits);
+
+    let counter = cache.entry::<&str, HitCounter>("entry").unwrap()
+        .and_modify(|c| c.hits += 1)
+        .or_insert_with(|| HitCounter { hits: 0 }).unwrap();
+    assert_eq!(1, counter.hits);
+
+    let stored: HitCounter = cache.get("entry").unwrap().unwrap();
+    assert_eq!(1, stored.hits);
+
+    match cache.entry::<&str, HitCounter>("entry").unwrap() {
+        Entry::Occupied(e) => assert_eq!(1, e.remove().unwrap().hits),
+        Entry::Vacant(_) => assert!(false),
+    }
+
+    assert!(cache.get::<&str, HitCounter>("entry").unwrap().is_none());
+}
+
+#[test]
+fn redis_cache_entry_test() {
+    use mouscache::Entry;
+
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.remove::<&str, HitCounter>("entry");
+
+    let counter = cache.entry::<&str, HitCounter>("entry").unwrap()
+        .and_modify(|c| c.hits += 1)
+        .or_insert(HitCounter { hits: 0 }).unwrap();
+    assert_eq!(0, counter.hits);
+
+    let counter = cache.entry::<&str, HitCounter>("entry").unwrap()
+        .and_modify(|c| c.hits += 1)
+        .or_insert_with(|| HitCounter { hits: 0 }).unwrap();
+    assert_eq!(1, counter.hits);
+
+    let stored: HitCounter = cache.get("entry").unwrap().unwrap();
+    assert_eq!(1, stored.hits);
+
+    match cache.entry::<&str, HitCounter>("entry").unwrap() {
+        Entry::Occupied(e) => {
+            // A write made behind the entry's back aborts its transaction
+            let _ = cache.insert("entry", HitCounter { hits: 42 });
+            match e.remove() {
+                Err(mouscache::CacheError::ConflictError(_)) => {}
+                other => panic!("expected a conflict, got {:?}", other.map(|c| c.hits)),
+            }
+        }
+        Entry::Vacant(_) => assert!(false),
+    }
+
+    let stored: HitCounter = cache.get("entry").unwrap().unwrap();
+    assert_eq!(42, stored.hits);
+}
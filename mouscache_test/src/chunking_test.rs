@@ -0,0 +1,69 @@
+use mouscache;
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(storage = "blob", expires = "60")]
+struct Dataset {
+    name: String,
+    rows: Vec<String>,
+}
+
+fn dataset(name: &str, rows: usize) -> Dataset {
+    Dataset {
+        name: name.to_string(),
+        rows: (0..rows).map(|i| format!("row {}", i)).collect(),
+    }
+}
+
+fn chunking_test(cache: &mouscache::Cache) {
+    let _ = cache.remove::<&str, Dataset>("big");
+
+    cache.insert("big", dataset("big", 1000)).unwrap();
+    assert_eq!(Some(dataset("big", 1000)), cache.get("big").unwrap());
+
+    cache.update::<_, Dataset, _>("big", |d| d.rows.truncate(10)).unwrap();
+    assert_eq!(Some(dataset("big", 10)), cache.get("big").unwrap());
+
+    assert_eq!(Some(dataset("big", 10)), cache.swap("big", dataset("big", 2000)).unwrap());
+    assert_eq!(Some(dataset("big", 2000)), cache.take::<_, Dataset>("big").unwrap());
+    assert_eq!(None, cache.get::<_, Dataset>("big").unwrap());
+}
+
+#[test]
+fn memory_cache_chunking_test() {
+    let cache = mouscache::memory().with_chunking(1024);
+    chunking_test(&cache);
+}
+
+#[test]
+fn redis_cache_chunking_test() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let chunked = cache.clone().with_chunking(1024);
+    chunking_test(&chunked);
+
+    // Chunks are reassembled whatever the chunk size of the cache reading them
+    chunked.insert("split", dataset("split", 1000)).unwrap();
+    assert_eq!(Some(dataset("split", 1000)), cache.get("split").unwrap());
+
+    // Chunks don't share keys with objects
+    cache.insert("split:chunk:1", dataset("other", 1)).unwrap();
+    assert_eq!(Some(dataset("split", 1000)), chunked.get("split").unwrap());
+    cache.remove::<_, Dataset>("split:chunk:1").unwrap();
+    assert_eq!(Some(dataset("split", 1000)), chunked.get("split").unwrap());
+
+    // A missing chunk fails the checksum
+    let client = redis::Client::open("redis://:123456@localhost/").unwrap();
+    let con = client.get_connection().unwrap();
+    redis::cmd("DEL").arg(format!("{{Dataset:split}}\0chunk\0{}", 1)).query::<()>(&con).unwrap();
+    match chunked.get::<_, Dataset>("split") {
+        Err(mouscache::CacheError::IntegrityError(_)) => {}
+        other => panic!("expected an integrity error, got {:?}", other),
+    }
+    chunked.remove::<_, Dataset>("split").unwrap();
+}
@@ -0,0 +1,98 @@
+use mouscache;
+use mouscache::CacheFunc;
+use mouscache::compression::{Algorithm, Compression};
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct Report {
+    #[cache(index)]
+    owner: String,
+    title: String,
+    body: String,
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(storage = "blob")]
+struct Archive {
+    lines: Vec<String>,
+}
+
+fn report(owner: &str, title: &str) -> Report {
+    Report {
+        owner: owner.to_string(),
+        title: title.to_string(),
+        body: "All work and no play makes Jack a dull boy. ".repeat(200),
+    }
+}
+
+fn archive() -> Archive {
+    Archive {
+        lines: (0..500).map(|i| format!("line {}", i)).collect(),
+    }
+}
+
+fn compression_test(cache: mouscache::Cache, algorithm: Algorithm) {
+    let _ = cache.remove::<&str, Report>("old");
+    let _ = cache.remove::<&str, Report>("new");
+    let _ = cache.remove::<&str, Archive>("old");
+    let _ = cache.remove::<&str, Archive>("new");
+
+    // Written before compression is turned on
+    cache.insert("old", report("alice", "Q1")).unwrap();
+    cache.insert("old", archive()).unwrap();
+
+    let cache = cache.with_compression(Compression::new(algorithm, 256));
+
+    cache.insert("new", report("alice", "Q2")).unwrap();
+    cache.insert("new", archive()).unwrap();
+
+    assert_eq!(Some(report("alice", "Q1")), cache.get("old").unwrap());
+    assert_eq!(Some(report("alice", "Q2")), cache.get("new").unwrap());
+    assert_eq!(Some(archive()), cache.get("old").unwrap());
+    assert_eq!(Some(archive()), cache.get("new").unwrap());
+
+    assert_eq!(Some(report("alice", "Q2").body), cache.get_field::<_, Report>("new", "body").unwrap());
+    assert_eq!(2, cache.find_by::<Report, _>("owner", "alice").unwrap().len());
+
+    cache.update::<_, Report, _>("old", |r| r.owner = String::from("bob")).unwrap();
+    assert_eq!(Some(report("bob", "Q1")), cache.get("old").unwrap());
+    assert_eq!(vec![report("alice", "Q2")], cache.find_by::<Report, _>("owner", "alice").unwrap());
+
+    assert!(cache.set_field::<_, Report, _>("new", "body", "short").unwrap());
+    assert_eq!(Some(String::from("short")), cache.get_field::<_, Report>("new", "body").unwrap());
+
+    let mut updated = archive();
+    updated.lines.push(String::from("last"));
+    assert_eq!(Some(archive()), cache.swap("new", updated.clone()).unwrap());
+    assert_eq!(Some(updated), cache.take::<_, Archive>("new").unwrap());
+}
+
+#[test]
+fn memory_cache_lz4_compression_test() {
+    compression_test(mouscache::memory(), Algorithm::Lz4);
+}
+
+#[test]
+fn memory_cache_zstd_compression_test() {
+    compression_test(mouscache::memory(), Algorithm::Zstd(0));
+}
+
+#[test]
+fn redis_cache_compression_test() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    compression_test(cache.clone(), Algorithm::Lz4);
+    compression_test(cache.clone(), Algorithm::Zstd(0));
+
+    // Small values and indexed fields are stored as is
+    let cache = cache.with_compression(Compression::new(Algorithm::Lz4, 256));
+    cache.insert("raw", report("carol", "Q3")).unwrap();
+    assert_eq!(Some(String::from("Q3")), cache.hash_get("Report:raw", "title").unwrap());
+    assert_eq!(Some(String::from("carol")), cache.hash_get("Report:raw", "owner").unwrap());
+    assert!(cache.hash_get::<String>("Report:raw", "body").is_err());
+}
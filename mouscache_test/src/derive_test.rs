@@ -122,4 +122,951 @@ fn redis_cache_test_db() {
 
     assert_eq!(data.field1, data2.field1);
     assert_eq!(data.field2, data2.field2);
-}
\ No newline at end of file
+}
+#[derive(Cacheable, Clone, Debug)]
+struct DataTestOptional {
+    name: String,
+    nickname: Option<String>,
+    age: Option<u8>,
+}
+
+#[derive(Cacheable, Clone, Debug)]
+struct DataTestAllOptional {
+    nickname: Option<String>,
+}
+
+#[test]
+fn memory_cache_test_derive_optional() {
+    use mouscache::Cacheable;
+
+    let data = DataTestOptional {
+        name: String::from("alice"),
+        nickname: None,
+        age: Some(42),
+    };
+
+    let obj = data.to_redis_obj();
+    assert_eq!(2, obj.len());
+    assert!(obj.iter().all(|&(ref f, _)| f != "nickname"));
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("test", data.clone());
+
+    let data2: DataTestOptional = cache.get("test").unwrap().unwrap();
+    assert_eq!(data.nickname, data2.nickname);
+    assert_eq!(data.age, data2.age);
+
+    assert!(DataTestOptional::set_nickname(&cache, "test", Some(String::from("al"))).unwrap());
+    assert_eq!(Some(String::from("al")), DataTestOptional::get_nickname(&cache, "test").unwrap());
+    assert!(DataTestOptional::set_age(&cache, "test", None).unwrap());
+    assert_eq!(None, DataTestOptional::get_age(&cache, "test").unwrap());
+
+    // Entries cached before a field was added still load, with the new field as None
+    let mut old_entry = std::collections::HashMap::new();
+    old_entry.insert(String::from("name"), String::from("bob"));
+    let data3 = DataTestOptional::from_redis_obj(old_entry).unwrap();
+    assert_eq!("bob", data3.name);
+    assert_eq!(None, data3.nickname);
+    assert_eq!(None, data3.age);
+}
+
+#[test]
+fn redis_cache_test_derive_optional() {
+    let data = DataTestOptional {
+        name: String::from("alice"),
+        nickname: Some(String::from("al")),
+        age: None,
+    };
+
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.insert("test", data.clone());
+
+    let data2: DataTestOptional = cache.get("test").unwrap().unwrap();
+    assert_eq!(data.nickname, data2.nickname);
+    assert_eq!(data.age, data2.age);
+
+    // Replacing the entry must not leave the previous nickname behind
+    let _ = cache.insert("test", DataTestOptional { nickname: None, ..data.clone() });
+    let data3: DataTestOptional = cache.get("test").unwrap().unwrap();
+    assert_eq!(None, data3.nickname);
+
+    let _ = cache.insert("empty", DataTestAllOptional { nickname: None });
+    let empty: DataTestAllOptional = cache.get("empty").unwrap().unwrap();
+    assert_eq!(None, empty.nickname);
+}
+
+#[derive(Cacheable, Clone, Debug)]
+struct DataTestCollections {
+    tags: Vec<String>,
+    scores: Vec<u32>,
+    labels: std::collections::HashMap<String, String>,
+    roles: std::collections::HashSet<String>,
+    aliases: Option<Vec<String>>,
+}
+
+fn collections_data() -> DataTestCollections {
+    let mut labels = std::collections::HashMap::new();
+    labels.insert(String::from("env:prod"), String::from("a,b;c"));
+    labels.insert(String::from(""), String::from("12:34"));
+
+    DataTestCollections {
+        tags: vec![String::from("a:b"), String::new(), String::from("3:x"), String::from("ünïcödé")],
+        scores: vec![1, 22, 333],
+        labels,
+        roles: vec![String::from("admin"), String::from("1:")].into_iter().collect(),
+        aliases: Some(vec![]),
+    }
+}
+
+#[test]
+fn memory_cache_test_derive_collections() {
+    use mouscache::Cacheable;
+
+    let data = collections_data();
+
+    let data2 = DataTestCollections::from_redis_obj(data.to_redis_obj().into_iter().collect()).unwrap();
+    assert_eq!(data.tags, data2.tags);
+    assert_eq!(data.scores, data2.scores);
+    assert_eq!(data.labels, data2.labels);
+    assert_eq!(data.roles, data2.roles);
+    assert_eq!(data.aliases, data2.aliases);
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("test", data.clone());
+
+    assert!(DataTestCollections::set_scores(&cache, "test", vec![4, 5]).unwrap());
+    assert_eq!(Some(vec![4, 5]), DataTestCollections::get_scores(&cache, "test").unwrap());
+    assert_eq!(Some(data.tags.clone()), DataTestCollections::get_tags(&cache, "test").unwrap());
+}
+
+#[test]
+fn redis_cache_test_derive_collections() {
+    let data = collections_data();
+
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.insert("test", data.clone());
+
+    let data2: DataTestCollections = cache.get("test").unwrap().unwrap();
+    assert_eq!(data.tags, data2.tags);
+    assert_eq!(data.scores, data2.scores);
+    assert_eq!(data.labels, data2.labels);
+    assert_eq!(data.roles, data2.roles);
+    assert_eq!(data.aliases, data2.aliases);
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct Address {
+    street: String,
+    city: String,
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct DataTestNested {
+    name: String,
+    #[cache(flatten)]
+    home: Address,
+    #[cache(nested)]
+    work: Option<Address>,
+}
+
+fn nested_data() -> DataTestNested {
+    DataTestNested {
+        name: String::from("john"),
+        home: Address {
+            street: String::from("1:2 Main St."),
+            city: String::from("Springfield"),
+        },
+        work: Some(Address {
+            street: String::from("3 Office Rd."),
+            city: String::from("Shelbyville"),
+        }),
+    }
+}
+
+#[test]
+fn memory_cache_test_derive_nested() {
+    use mouscache::Cacheable;
+
+    let data = nested_data();
+
+    let fields: Vec<String> = data.to_redis_obj().into_iter().map(|(k, _)| k).collect();
+    assert!(fields.contains(&String::from("home.street")));
+    assert!(fields.contains(&String::from("home.city")));
+    assert!(fields.contains(&String::from("work")));
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("test", data.clone());
+
+    let data2: DataTestNested = cache.get("test").unwrap().unwrap();
+    assert_eq!(data, data2);
+
+    assert!(DataTestNested::set_work(&cache, "test", None).unwrap());
+    let data3: DataTestNested = cache.get("test").unwrap().unwrap();
+    assert_eq!(None, data3.work);
+    assert_eq!(data.home, data3.home);
+}
+
+// `Cacheable` fields without an attribute are stored like `nested` ones
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct DataTestImplicitNested {
+    home: Address,
+    work: Option<Address>,
+}
+
+#[test]
+fn memory_cache_test_derive_implicit_nested() {
+    use mouscache::Cacheable;
+
+    let data = DataTestImplicitNested {
+        home: nested_data().home,
+        work: nested_data().work,
+    };
+
+    let fields: std::collections::HashMap<String, String> = data.to_redis_obj().into_iter().collect();
+    assert_eq!(mouscache::codec::encode_object(&data.home).unwrap(), fields["home"]);
+    assert_eq!(data, DataTestImplicitNested::from_redis_obj(fields).unwrap());
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("test", data.clone());
+    assert_eq!(Some(data.home.clone()), DataTestImplicitNested::get_home(&cache, "test").unwrap());
+    assert!(DataTestImplicitNested::set_work(&cache, "test", None).unwrap());
+    let data2: DataTestImplicitNested = cache.get("test").unwrap().unwrap();
+    assert_eq!(None, data2.work);
+    assert_eq!(data.home, data2.home);
+}
+
+#[test]
+fn redis_cache_test_derive_nested() {
+    let data = nested_data();
+
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.insert("test", data.clone());
+
+    let data2: DataTestNested = cache.get("test").unwrap().unwrap();
+    assert_eq!(data, data2);
+    assert_eq!(data.work, DataTestNested::get_work(&cache, "test").unwrap());
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+enum DataTestEnum {
+    Empty,
+    Point(i32, i32),
+    Named {
+        name: String,
+        aliases: Vec<String>,
+        nickname: Option<String>,
+    },
+}
+
+#[test]
+fn memory_cache_test_derive_enum() {
+    use std::collections::HashMap;
+    use mouscache::Cacheable;
+
+    let values = vec![
+        DataTestEnum::Empty,
+        DataTestEnum::Point(-1, 42),
+        DataTestEnum::Named {
+            name: String::from("john"),
+            aliases: vec![String::from("jo"), String::from("j:d")],
+            nickname: None,
+        },
+    ];
+
+    let cache = mouscache::memory();
+
+    for value in values {
+        let fields: HashMap<String, String> = value.to_redis_obj().into_iter().collect();
+        assert_eq!(value, DataTestEnum::from_redis_obj(fields).unwrap());
+
+        let _ = cache.insert("test", value.clone());
+        let value2: DataTestEnum = cache.get("test").unwrap().unwrap();
+        assert_eq!(value, value2);
+    }
+
+    let mut fields = HashMap::new();
+    fields.insert(String::from(mouscache::VARIANT_FIELD), String::from("Unknown"));
+    assert!(DataTestEnum::from_redis_obj(fields).is_err());
+    assert!(DataTestEnum::from_redis_obj(HashMap::new()).is_err());
+}
+
+#[test]
+fn redis_cache_test_derive_enum() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let values = vec![
+        DataTestEnum::Empty,
+        DataTestEnum::Point(-1, 42),
+        DataTestEnum::Named {
+            name: String::from("john"),
+            aliases: vec![],
+            nickname: Some(String::from("jd")),
+        },
+    ];
+
+    for value in values {
+        let _ = cache.insert("test", value.clone());
+        let value2: DataTestEnum = cache.get("test").unwrap().unwrap();
+        assert_eq!(value, value2);
+    }
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct DataTestTuple(String, u32, Option<u8>);
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct DataTestNewtype(u64);
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct DataTestTransparent(#[cache(flatten)] Address);
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct DataTestUnit;
+
+#[test]
+fn memory_cache_test_derive_unnamed() {
+    use std::collections::HashMap;
+    use mouscache::Cacheable;
+
+    let tuple = DataTestTuple(String::from("a"), 7, None);
+    let fields: HashMap<String, String> = tuple.to_redis_obj().into_iter().collect();
+    assert_eq!(Some(&String::from("a")), fields.get("0"));
+    assert_eq!(Some(&String::from("7")), fields.get("1"));
+    assert_eq!(tuple, DataTestTuple::from_redis_obj(fields).unwrap());
+
+    let address = Address {
+        street: String::from("1 Main St."),
+        city: String::from("Springfield"),
+    };
+    let transparent = DataTestTransparent(address.clone());
+    assert_eq!(address.to_redis_obj(), transparent.to_redis_obj());
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("tuple", tuple.clone());
+    let _ = cache.insert("newtype", DataTestNewtype(42));
+    let _ = cache.insert("transparent", transparent.clone());
+    let _ = cache.insert("unit", DataTestUnit);
+
+    assert_eq!(Some(tuple), cache.get::<_, DataTestTuple>("tuple").unwrap());
+    assert_eq!(Some(DataTestNewtype(42)), cache.get::<_, DataTestNewtype>("newtype").unwrap());
+    assert_eq!(Some(transparent), cache.get::<_, DataTestTransparent>("transparent").unwrap());
+    assert_eq!(Some(DataTestUnit), cache.get::<_, DataTestUnit>("unit").unwrap());
+    assert_eq!(None, cache.get::<_, DataTestUnit>("missing").unwrap());
+}
+
+#[test]
+fn redis_cache_test_derive_unnamed() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let tuple = DataTestTuple(String::from("a"), 7, Some(3));
+
+    let _ = cache.insert("tuple", tuple.clone());
+    let _ = cache.insert("newtype", DataTestNewtype(42));
+    let _ = cache.insert("unit", DataTestUnit);
+    let _ = cache.remove::<_, DataTestUnit>("missing");
+
+    assert_eq!(Some(tuple), cache.get::<_, DataTestTuple>("tuple").unwrap());
+    assert_eq!(Some(DataTestNewtype(42)), cache.get::<_, DataTestNewtype>("newtype").unwrap());
+    assert_eq!(Some(DataTestUnit), cache.get::<_, DataTestUnit>("unit").unwrap());
+    assert_eq!(None, cache.get::<_, DataTestUnit>("missing").unwrap());
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(rename = "Envelope")]
+struct DataTestEnvelope<T, P> where P: Clone {
+    id: T,
+    history: Vec<T>,
+    #[cache(flatten)]
+    payload: P,
+}
+
+#[test]
+fn memory_cache_test_derive_generic() {
+    use mouscache::Cacheable;
+
+    assert_eq!("Envelope<u64,mouscache_test::derive_test::Address>", DataTestEnvelope::<u64, Address>::model_name());
+    assert_eq!("Envelope<alloc::string::String,mouscache_test::derive_test::DataTestNewtype>", DataTestEnvelope::<String, DataTestNewtype>::model_name());
+
+    let address = DataTestEnvelope {
+        id: 1u64,
+        history: vec![2, 3],
+        payload: Address {
+            street: String::from("1 Main St."),
+            city: String::from("Springfield"),
+        },
+    };
+    let newtype = DataTestEnvelope {
+        id: 1u64,
+        history: vec![],
+        payload: DataTestNewtype(7),
+    };
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("test", address.clone());
+    let _ = cache.insert("test", newtype.clone());
+
+    assert_eq!(Some(address), cache.get::<_, DataTestEnvelope<u64, Address>>("test").unwrap());
+    assert_eq!(Some(newtype), cache.get::<_, DataTestEnvelope<u64, DataTestNewtype>>("test").unwrap());
+    assert_eq!(Some(vec![]), DataTestEnvelope::<u64, DataTestNewtype>::get_history(&cache, "test").unwrap());
+    assert_eq!(None, cache.get::<_, DataTestEnvelope<String, Address>>("test").unwrap());
+}
+
+// Stored under the same model name as `Address`
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(rename = "Address")]
+struct DataTestAddressAlias {
+    city: String,
+}
+
+#[test]
+fn memory_cache_test_derive_type_mismatch() {
+    let cache = mouscache::memory();
+    let _ = cache.insert("home", Address {
+        street: String::from("1 Main St."),
+        city: String::from("Springfield"),
+    });
+
+    assert!(cache.get::<_, DataTestAddressAlias>("home").is_err());
+}
+
+#[test]
+fn redis_cache_test_derive_generic() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let address = DataTestEnvelope {
+        id: String::from("a"),
+        history: vec![String::from("b")],
+        payload: Address {
+            street: String::from("1 Main St."),
+            city: String::from("Springfield"),
+        },
+    };
+    let newtype = DataTestEnvelope {
+        id: String::from("a"),
+        history: vec![],
+        payload: DataTestNewtype(7),
+    };
+
+    let _ = cache.insert("test", address.clone());
+    let _ = cache.insert("test", newtype.clone());
+
+    assert_eq!(Some(address), cache.get::<_, DataTestEnvelope<String, Address>>("test").unwrap());
+    assert_eq!(Some(newtype), cache.get::<_, DataTestEnvelope<String, DataTestNewtype>>("test").unwrap());
+}
+
+fn default_retries() -> u8 {
+    3
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct DataTestFieldAttributes {
+    #[cache(rename = "user_name")]
+    name: String,
+    #[cache(skip)]
+    session_hits: u32,
+    #[cache(default = "default_retries()")]
+    retries: u8,
+    #[cache(default)]
+    tags: Vec<String>,
+    #[cache(rename = "addr", default = "Address { street: String::new(), city: String::from(\"?\") }")]
+    #[cache(flatten)]
+    address: Address,
+}
+
+#[test]
+fn memory_cache_test_derive_field_attributes() {
+    use std::collections::HashMap;
+    use mouscache::Cacheable;
+
+    let data = DataTestFieldAttributes {
+        name: String::from("john"),
+        session_hits: 12,
+        retries: 5,
+        tags: vec![String::from("a")],
+        address: Address {
+            street: String::from("1 Main St."),
+            city: String::from("Springfield"),
+        },
+    };
+
+    let fields: HashMap<String, String> = data.to_redis_obj().into_iter().collect();
+    assert_eq!(Some(&String::from("john")), fields.get("user_name"));
+    assert_eq!(Some(&String::from("Springfield")), fields.get("addr.city"));
+    assert!(!fields.contains_key("name"));
+    assert!(!fields.contains_key("session_hits"));
+
+    let data2 = DataTestFieldAttributes::from_redis_obj(fields).unwrap();
+    assert_eq!(0, data2.session_hits);
+    assert_eq!(DataTestFieldAttributes { session_hits: 0, ..data.clone() }, data2);
+
+    // An entry cached before `retries`, `tags` and `address` were added
+    let mut old_fields = HashMap::new();
+    old_fields.insert(String::from("user_name"), String::from("john"));
+    let old = DataTestFieldAttributes::from_redis_obj(old_fields).unwrap();
+    assert_eq!(3, old.retries);
+    assert!(old.tags.is_empty());
+    assert_eq!("?", old.address.city);
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("test", data.clone());
+    assert!(DataTestFieldAttributes::set_name(&cache, "test", String::from("jane")).unwrap());
+    assert_eq!(Some(String::from("jane")), DataTestFieldAttributes::get_name(&cache, "test").unwrap());
+}
+
+#[test]
+fn redis_cache_test_derive_field_attributes() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let data = DataTestFieldAttributes {
+        name: String::from("john"),
+        session_hits: 12,
+        retries: 5,
+        tags: vec![],
+        address: Address {
+            street: String::from("1 Main St."),
+            city: String::from("Springfield"),
+        },
+    };
+
+    let _ = cache.insert("test", data.clone());
+
+    let data2: DataTestFieldAttributes = cache.get("test").unwrap().unwrap();
+    assert_eq!(DataTestFieldAttributes { session_hits: 0, ..data.clone() }, data2);
+    assert_eq!(Some(String::from("john")), DataTestFieldAttributes::get_name(&cache, "test").unwrap());
+}
+
+/// Stores degrees as tenths, which `f32`'s `ToString` doesn't do
+mod tenths {
+    use mouscache::{CacheError, Result};
+
+    pub fn encode(value: &f32) -> String {
+        ((value * 10.0).round() as i64).to_string()
+    }
+
+    pub fn decode(raw: &str) -> Result<f32> {
+        raw.parse::<i64>().map(|v| v as f32 / 10.0).map_err(|_| CacheError::Other(format!("Invalid tenths {:?}", raw)))
+    }
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct DataTestCodec {
+    #[cache(with = "tenths")]
+    temperature: f32,
+    #[cache(with = "mouscache::codec::Hex")]
+    checksum: Vec<u8>,
+    #[cache(with = "mouscache::codec::Hex")]
+    signature: Option<Vec<u8>>,
+}
+
+#[test]
+fn memory_cache_test_derive_codec() {
+    use std::collections::HashMap;
+    use mouscache::Cacheable;
+    use mouscache::codec::{Codec, Hex};
+
+    assert_eq!("00ff10", Hex::encode(&vec![0, 255, 16]).unwrap());
+    assert_eq!(vec![0, 255, 16], Hex::decode("00FF10").unwrap());
+    assert!(Hex::decode("0").is_err());
+
+    let data = DataTestCodec {
+        temperature: 21.5,
+        checksum: vec![0xde, 0xad, 0xbe, 0xef],
+        signature: None,
+    };
+
+    let fields: HashMap<String, String> = data.to_redis_obj().into_iter().collect();
+    assert_eq!(Some(&String::from("215")), fields.get("temperature"));
+    assert_eq!(Some(&String::from("deadbeef")), fields.get("checksum"));
+    assert_eq!(data, DataTestCodec::from_redis_obj(fields).unwrap());
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("test", data.clone());
+    assert!(DataTestCodec::set_signature(&cache, "test", Some(vec![1, 2])).unwrap());
+    assert_eq!(Some(vec![1, 2]), DataTestCodec::get_signature(&cache, "test").unwrap());
+    assert_eq!(Some(21.5), DataTestCodec::get_temperature(&cache, "test").unwrap());
+}
+
+#[test]
+fn redis_cache_test_derive_codec() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let data = DataTestCodec {
+        temperature: -3.2,
+        checksum: vec![],
+        signature: Some(vec![0, 1]),
+    };
+
+    let _ = cache.insert("test", data.clone());
+
+    let data2: DataTestCodec = cache.get("test").unwrap().unwrap();
+    assert_eq!(data, data2);
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct DataTestKeyed {
+    #[cache(key)]
+    id: u64,
+    name: String,
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct DataTestKeyedTuple(#[cache(key)] String, u32);
+
+fn keyed_test(cache: &mouscache::Cache) {
+    use mouscache::CacheKey;
+
+    let data = DataTestKeyed {
+        id: 42,
+        name: String::from("john"),
+    };
+    assert_eq!("42", data.cache_key());
+    assert_eq!("a", DataTestKeyedTuple(String::from("a"), 1).cache_key());
+
+    cache.put(data.clone()).unwrap();
+    assert_eq!(Some(data.clone()), cache.get::<_, DataTestKeyed>(42).unwrap());
+
+    let _ = cache.insert(42, DataTestKeyed { name: String::from("jane"), ..data.clone() });
+    assert_eq!(Some(String::from("jane")), cache.refresh(&data).unwrap().map(|d| d.name));
+
+    cache.delete_obj(&data).unwrap();
+    assert_eq!(None, cache.refresh(&data).unwrap());
+}
+
+#[test]
+fn memory_cache_test_derive_key() {
+    keyed_test(&mouscache::memory());
+}
+
+#[test]
+fn redis_cache_test_derive_key() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    keyed_test(&cache);
+}
+
+#[derive(Cacheable, Clone, Debug)]
+#[cache(expires_field = "ttl_secs")]
+struct DataTestToken {
+    value: String,
+    ttl_secs: u32,
+}
+
+#[derive(Cacheable, Clone, Debug)]
+#[cache(expires_field = "ttl_secs")]
+struct DataTestOptionalTtl {
+    ttl_secs: Option<u64>,
+}
+
+#[derive(Cacheable, Clone, Debug)]
+#[cache(expires_field = "ttl_secs")]
+struct DataTestSignedTtl {
+    ttl_secs: i64,
+}
+
+#[derive(Cacheable, Clone, Debug)]
+#[cache(expires_fn = "Self::compute_ttl")]
+struct DataTestComputedTtl {
+    permanent: bool,
+}
+
+impl DataTestComputedTtl {
+    fn compute_ttl(&self) -> Option<usize> {
+        if self.permanent {
+            None
+        } else {
+            Some(1)
+        }
+    }
+}
+
+#[test]
+fn memory_cache_test_derive_instance_expires() {
+    use mouscache::Cacheable;
+
+    let short = DataTestToken { value: String::from("a"), ttl_secs: 1 };
+    let long = DataTestToken { value: String::from("b"), ttl_secs: 60 };
+    assert_eq!(Some(1), short.expires_after());
+    assert_eq!(Some(60), long.expires_after());
+    assert_eq!(None, DataTestOptionalTtl { ttl_secs: None }.expires_after());
+    assert_eq!(Some(5), DataTestOptionalTtl { ttl_secs: Some(5) }.expires_after());
+    assert_eq!(Some(0), DataTestOptionalTtl { ttl_secs: Some(0) }.expires_after());
+    assert_eq!(Some(30), DataTestSignedTtl { ttl_secs: 30 }.expires_after());
+    assert_eq!(None, DataTestSignedTtl { ttl_secs: -1 }.expires_after());
+    assert_eq!(None, DataTestComputedTtl { permanent: true }.expires_after());
+    assert_eq!(Some(1), DataTestComputedTtl { permanent: false }.expires_after());
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("short", short);
+    let _ = cache.insert("long", long);
+
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    assert!(cache.get::<_, DataTestToken>("short").unwrap().is_none());
+    assert!(cache.get::<_, DataTestToken>("long").unwrap().is_some());
+}
+
+#[test]
+fn redis_cache_test_derive_instance_expires() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.insert("short", DataTestComputedTtl { permanent: false });
+    let _ = cache.insert("long", DataTestComputedTtl { permanent: true });
+
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    assert!(cache.get::<_, DataTestComputedTtl>("short").unwrap().is_none());
+    assert!(cache.get::<_, DataTestComputedTtl>("long").unwrap().is_some());
+}
+
+// A ttl of 0 means the entry is already expired, writing it deletes what was cached
+fn zero_ttl_test(cache: &mouscache::Cache) {
+    let _ = cache.insert("zero", DataTestToken { value: String::from("a"), ttl_secs: 60 });
+    assert!(cache.get::<_, DataTestToken>("zero").unwrap().is_some());
+
+    cache.insert("zero", DataTestToken { value: String::from("b"), ttl_secs: 0 }).unwrap();
+    assert!(cache.get::<_, DataTestToken>("zero").unwrap().is_none());
+
+    cache.insert_with("zero", DataTestComputedTtl { permanent: true }, Some(0)).unwrap();
+    assert!(cache.get::<_, DataTestComputedTtl>("zero").unwrap().is_none());
+
+    let version = cache.insert_if_version("zero", DataTestToken { value: String::from("c"), ttl_secs: 0 }, 0).unwrap();
+    assert_eq!(0, version);
+    assert!(cache.get_versioned::<_, DataTestToken>("zero").unwrap().is_none());
+
+    cache.insert_with("zero", DataTestComputedTtl { permanent: true }, None).unwrap();
+    assert!(cache.get::<_, DataTestComputedTtl>("zero").unwrap().is_some());
+    let _ = cache.remove::<_, DataTestComputedTtl>("zero");
+}
+
+#[test]
+fn memory_cache_test_derive_zero_ttl() {
+    zero_ttl_test(&mouscache::memory());
+}
+
+#[test]
+fn redis_cache_test_derive_zero_ttl() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    zero_ttl_test(&cache);
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(version = 2, migrate = "migrate_profile")]
+struct DataTestProfile {
+    display_name: String,
+    age: u8,
+}
+
+// Version 1 stored `name` and had no `age`, entries older than that are dropped
+fn migrate_profile(from: u32, mut obj: std::collections::HashMap<String, String>) -> Option<std::collections::HashMap<String, String>> {
+    if from != 1 {
+        return None;
+    }
+
+    let name = obj.remove("name")?;
+    obj.insert(String::from("display_name"), name);
+    obj.insert(String::from("age"), String::from("0"));
+    Some(obj)
+}
+
+#[test]
+fn memory_cache_test_derive_schema_version() {
+    use mouscache::Cacheable;
+
+    assert_eq!(2, DataTestProfile::schema_version());
+    assert_eq!(0, DataTestDerive::schema_version());
+
+    let old = vec![(String::from("name"), String::from("Alice"))].into_iter().collect();
+    let migrated = DataTestProfile::migrate(1, old).unwrap();
+    let profile = DataTestProfile::from_redis_obj(migrated).unwrap();
+    assert_eq!(DataTestProfile { display_name: String::from("Alice"), age: 0 }, profile);
+    assert!(DataTestProfile::migrate(0, std::collections::HashMap::new()).is_none());
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("alice", profile.clone());
+    assert_eq!(Some(profile), cache.get::<_, DataTestProfile>("alice").unwrap());
+}
+
+#[test]
+fn redis_cache_test_derive_schema_version() {
+    use mouscache::CacheFunc;
+
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.insert("bob", DataTestProfile { display_name: String::from("Bob"), age: 42 });
+    assert_eq!(Some(String::from("2")), cache.get_field::<_, DataTestProfile>("bob", mouscache::SCHEMA_FIELD).unwrap());
+
+    // Entries written before the schema was versioned
+    let _ = cache.hash_multiple_set("DataTestProfile:v1", &[("name", "Alice"), (mouscache::SCHEMA_FIELD, "1")]);
+    let _ = cache.hash_multiple_set("DataTestProfile:v0", &[("display_name", "Carol")]);
+    let _ = cache.hash_multiple_set("DataTestProfile:v3", &[("display_name", "Dave"), ("age", "1"), (mouscache::SCHEMA_FIELD, "3")]);
+
+    let alice = cache.get::<_, DataTestProfile>("v1").unwrap().unwrap();
+    assert_eq!(DataTestProfile { display_name: String::from("Alice"), age: 0 }, alice);
+    assert!(cache.get::<_, DataTestProfile>("v0").unwrap().is_none());
+    assert!(cache.get::<_, DataTestProfile>("v3").unwrap().is_none());
+
+    // Single fields of older entries are read and written through `migrate`, newer entries are left alone
+    assert_eq!(Some(String::from("Alice")), cache.get_field::<_, DataTestProfile>("v1", "display_name").unwrap());
+    assert!(cache.set_field::<_, DataTestProfile, _>("v1", "age", 30).unwrap());
+    assert_eq!(Some(DataTestProfile { display_name: String::from("Alice"), age: 30 }), cache.get("v1").unwrap());
+    assert_eq!(Some(String::from("2")), cache.get_field::<_, DataTestProfile>("v1", mouscache::SCHEMA_FIELD).unwrap());
+
+    assert_eq!(None, cache.get_field::<_, DataTestProfile>("v3", "display_name").unwrap());
+    assert!(!cache.set_field::<_, DataTestProfile, _>("v3", "age", 2).unwrap());
+    assert_eq!(Some(1), cache.hash_get::<u8>("DataTestProfile:v3", "age").unwrap());
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(storage = "blob", expires = "60")]
+struct DataTestBlob {
+    title: String,
+    tags: Vec<String>,
+    #[cache(nested)]
+    address: Option<Address>,
+}
+
+fn blob() -> DataTestBlob {
+    DataTestBlob {
+        title: String::from("line 1\nline 2:\t\u{0}"),
+        tags: vec![String::from("a:b"), String::new()],
+        address: Some(Address { street: String::from("Main St."), city: String::from("Montreal") }),
+    }
+}
+
+// Blobs are read and written whole, by both caches
+fn blob_unsupported_test(cache: &mouscache::Cache) {
+    assert!(cache.get_field::<_, DataTestBlob>("blob", "title").is_err());
+    assert!(cache.set_field::<_, DataTestBlob, _>("blob", "title", "other").is_err());
+    assert!(cache.delete_field::<_, DataTestBlob>("blob", "address").is_err());
+    assert!(cache.get_versioned::<_, DataTestBlob>("blob").is_err());
+    assert!(cache.insert_if_version("blob", blob(), 0).is_err());
+    assert!(cache.find_by::<DataTestBlob, _>("title", "other").is_err());
+    assert_eq!(Some(blob()), cache.get::<_, DataTestBlob>("blob").unwrap());
+}
+
+#[test]
+fn memory_cache_test_derive_blob() {
+    use mouscache::Cacheable;
+
+    assert_eq!(mouscache::Storage::Blob, DataTestBlob::storage());
+    assert_eq!(mouscache::Storage::Hash, DataTestDerive::storage());
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("blob", blob());
+    assert_eq!(Some(blob()), cache.get::<_, DataTestBlob>("blob").unwrap());
+    blob_unsupported_test(&cache);
+}
+
+#[test]
+fn redis_cache_test_derive_blob() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.remove::<_, DataTestBlob>("blob");
+
+    cache.insert("blob", blob()).unwrap();
+    assert_eq!(Some(blob()), cache.get::<_, DataTestBlob>("blob").unwrap());
+    blob_unsupported_test(&cache);
+
+    let status = cache.update::<_, DataTestBlob, _>("blob", |b| b.tags.push(String::from("c"))).unwrap();
+    assert!(status.existed);
+
+    let previous = cache.swap("blob", DataTestBlob { title: String::from("swapped"), tags: Vec::new(), address: None }).unwrap().unwrap();
+    assert_eq!(3, previous.tags.len());
+
+    let taken = cache.take::<_, DataTestBlob>("blob").unwrap().unwrap();
+    assert_eq!("swapped", taken.title);
+    assert!(cache.get::<_, DataTestBlob>("blob").unwrap().is_none());
+}
@@ -0,0 +1,126 @@
+use mouscache;
+use mouscache::CacheError;
+use std::collections::HashMap;
+use mouscache::encryption::StaticKeys;
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+struct Patient {
+    #[cache(index)]
+    ward: String,
+    name: String,
+    notes: Vec<String>,
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(storage = "blob")]
+struct Record {
+    ssn: String,
+}
+
+fn patient(ward: &str, name: &str) -> Patient {
+    Patient {
+        ward: ward.to_string(),
+        name: name.to_string(),
+        notes: vec![String::from("allergic to penicillin")],
+    }
+}
+
+fn is_decryption_error<T>(result: mouscache::Result<T>) -> bool {
+    matches!(result, Err(CacheError::DecryptionError(_)))
+}
+
+#[test]
+fn memory_cache_encryption_test() {
+    // Objects never leave the process, so the memory cache keeps them as is
+    let cache = mouscache::memory().with_encryption(StaticKeys::new(1, [1; 32]));
+    cache.insert("p1", patient("east", "Ada")).unwrap();
+    assert_eq!(Some(patient("east", "Ada")), cache.get("p1").unwrap());
+}
+
+#[test]
+fn redis_cache_encryption_test() {
+    let plain = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = plain.remove::<&str, Patient>("old");
+    plain.insert("old", patient("east", "Ada")).unwrap();
+
+    let cache = plain.clone().with_encryption(StaticKeys::new(1, [1; 32]));
+    cache.insert("p1", patient("east", "Bob")).unwrap();
+    cache.insert("p1", Record { ssn: String::from("078-05-1120") }).unwrap();
+
+    assert_eq!(Some(patient("east", "Bob")), cache.get("p1").unwrap());
+    assert_eq!(Some(String::from("Bob")), cache.get_field::<_, Patient>("p1", "name").unwrap());
+    // Entries written in clear aren't indexed by token
+    assert_eq!(vec![patient("east", "Bob")], cache.find_by::<Patient, _>("ward", "east").unwrap());
+
+    assert!(is_decryption_error(plain.get::<_, Patient>("p1")));
+    assert!(is_decryption_error(plain.get::<_, Record>("p1")));
+
+    let wrong_key = plain.clone().with_encryption(StaticKeys::new(1, [2; 32]));
+    assert!(is_decryption_error(wrong_key.get::<_, Patient>("p1")));
+    assert!(is_decryption_error(wrong_key.get_field::<_, Patient>("p1", "notes")));
+
+    // Entries written in clear are refused, unless they're being migrated
+    assert!(is_decryption_error(cache.get::<_, Patient>("old")));
+    assert!(is_decryption_error(cache.get_field::<_, Patient>("old", "name")));
+    let migrating = cache.clone().allow_plaintext_reads();
+    assert_eq!(Some(patient("east", "Ada")), migrating.get("old").unwrap());
+    assert_eq!(Some(patient("east", "Bob")), migrating.get("p1").unwrap());
+    migrating.insert("old", patient("east", "Ada")).unwrap();
+    assert_eq!(Some(patient("east", "Ada")), cache.get("old").unwrap());
+    assert_eq!(2, cache.find_by::<Patient, _>("ward", "east").unwrap().len());
+
+    // Values written under the previous key stay readable after a rotation
+    let rotated = plain.clone().with_encryption(StaticKeys::new(1, [1; 32]).rotate(2, [3; 32]));
+    rotated.insert("p2", patient("west", "Cy")).unwrap();
+    assert_eq!(Some(patient("east", "Bob")), rotated.get("p1").unwrap());
+    assert_eq!(Some(patient("west", "Cy")), rotated.get("p2").unwrap());
+    assert!(is_decryption_error(cache.get::<_, Patient>("p2")));
+    // Index tokens don't depend on the current key, entries written before the rotation are still found
+    assert_eq!(vec![patient("west", "Cy")], rotated.find_by::<Patient, _>("ward", "west").unwrap());
+    assert_eq!(2, rotated.find_by::<Patient, _>("ward", "east").unwrap().len());
+
+    // The index key outlives the key it was derived from
+    let retired = plain.with_encryption(StaticKeys::new(2, [3; 32]).with_index_key([1; 32]));
+    assert_eq!(Some(patient("west", "Cy")), retired.get("p2").unwrap());
+    assert_eq!(vec![patient("west", "Cy")], retired.find_by::<Patient, _>("ward", "west").unwrap());
+    assert!(is_decryption_error(retired.take::<_, Record>("p1")));
+}
+
+#[test]
+fn redis_cache_encryption_hides_values_test() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c.with_encryption(StaticKeys::new(1, [1; 32])),
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.remove::<&str, Patient>("p3");
+    cache.insert("p3", patient("radiology", "Dorothea")).unwrap();
+    assert!(cache.set_field::<_, Patient, _>("p3", "ward", "oncology").unwrap());
+    assert_eq!(vec![patient("oncology", "Dorothea")], cache.find_by::<Patient, _>("ward", "oncology").unwrap());
+    assert!(cache.find_by::<Patient, _>("ward", "radiology").unwrap().is_empty());
+
+    // Neither the stored fields nor the index keys reveal a value
+    let client = redis::Client::open("redis://:123456@localhost/").unwrap();
+    let con = client.get_connection().unwrap();
+    let keys: Vec<Vec<u8>> = redis::cmd("KEYS").arg("*").query(&con).unwrap();
+    let stored: HashMap<String, Vec<u8>> = redis::cmd("HGETALL").arg("Patient:p3").query(&con).unwrap();
+    assert!(!stored.is_empty());
+
+    let contains = |haystack: &[u8], needle: &str| haystack.windows(needle.len()).any(|w| w == needle.as_bytes());
+    for plaintext in &["radiology", "oncology", "Dorothea", "penicillin"] {
+        assert!(!keys.iter().any(|key| contains(key, plaintext)), "a key contains {}", plaintext);
+        assert!(!stored.values().any(|value| contains(value, plaintext)), "a field contains {}", plaintext);
+    }
+
+    cache.remove::<_, Patient>("p3").unwrap();
+}
@@ -0,0 +1,63 @@
+use mouscache;
+
+#[derive(Cacheable, Clone, Debug)]
+struct Report {
+    title: String,
+    views: u64,
+    body: String,
+}
+
+#[test]
+fn memory_cache_field_test() {
+    let cache = mouscache::memory();
+
+    assert_eq!(None, Report::get_views(&cache, "report").unwrap());
+    assert!(!Report::set_views(&cache, "report", 1).unwrap());
+
+    let _ = cache.insert("report", Report {
+        title: String::from("Weekly"),
+        views: 41,
+        body: String::from("A very long body"),
+    });
+
+    assert_eq!(Some(41), Report::get_views(&cache, "report").unwrap());
+    assert!(Report::set_views(&cache, "report", 42).unwrap());
+    assert_eq!(Some(String::from("Weekly")), Report::get_title(&cache, "report").unwrap());
+
+    let report: Report = cache.get("report").unwrap().unwrap();
+    assert_eq!(42, report.views);
+    assert_eq!("A very long body", report.body);
+
+    let (_, version) = cache.get_versioned::<&str, Report>("report").unwrap().unwrap();
+    assert_eq!(2, version);
+}
+
+#[test]
+fn redis_cache_field_test() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.remove::<&str, Report>("report");
+
+    assert_eq!(None, Report::get_views(&cache, "report").unwrap());
+    assert!(!Report::set_views(&cache, "report", 1).unwrap());
+
+    let _ = cache.insert("report", Report {
+        title: String::from("Weekly"),
+        views: 41,
+        body: String::from("A very long body"),
+    });
+
+    assert_eq!(Some(41), Report::get_views(&cache, "report").unwrap());
+    assert!(Report::set_views(&cache, "report", 42).unwrap());
+    assert_eq!(Some(String::from("Weekly")), Report::get_title(&cache, "report").unwrap());
+
+    let report: Report = cache.get("report").unwrap().unwrap();
+    assert_eq!(42, report.views);
+    assert_eq!("A very long body", report.body);
+}
@@ -0,0 +1,93 @@
+use std;
+use mouscache;
+
+#[derive(Cacheable, Clone, Debug)]
+struct Session {
+    #[cache(index)]
+    user: String,
+    #[cache(index, rename = "dev")]
+    device: u32,
+    token: String,
+}
+
+fn session(user: &str, device: u32, token: &str) -> Session {
+    Session {
+        user: user.to_string(),
+        device,
+        token: token.to_string(),
+    }
+}
+
+fn tokens(sessions: Vec<Session>) -> Vec<String> {
+    let mut tokens: Vec<String> = sessions.into_iter().map(|s| s.token).collect();
+    tokens.sort();
+    tokens
+}
+
+fn index_test(cache: &mouscache::Cache) {
+    for key in &["s1", "s2", "s3", "s4"] {
+        let _ = cache.remove::<&str, Session>(key);
+    }
+
+    assert!(cache.find_by::<Session, _>("token", "a").is_err());
+    assert!(cache.find_by::<Session, _>("user", "alice").unwrap().is_empty());
+
+    cache.insert("s1", session("alice", 1, "a")).unwrap();
+    cache.insert("s2", session("alice", 2, "b")).unwrap();
+    cache.insert("s3", session("bob", 1, "c")).unwrap();
+
+    assert_eq!(vec!["a", "b"], tokens(cache.find_by("user", "alice").unwrap()));
+    assert_eq!(vec!["a", "c"], tokens(cache.find_by("dev", 1).unwrap()));
+
+    // Replacing an entry moves it to the indexes of its new values
+    cache.insert("s2", session("bob", 2, "b")).unwrap();
+    assert_eq!(vec!["a"], tokens(cache.find_by("user", "alice").unwrap()));
+    assert_eq!(vec!["b", "c"], tokens(cache.find_by("user", "bob").unwrap()));
+
+    cache.update::<_, Session, _>("s3", |s| s.user = String::from("carol")).unwrap();
+    assert_eq!(vec!["b"], tokens(cache.find_by("user", "bob").unwrap()));
+
+    assert!(cache.set_field::<_, Session, _>("s1", "user", "carol").unwrap());
+    assert!(cache.find_by::<Session, _>("user", "alice").unwrap().is_empty());
+    assert_eq!(vec!["a", "c"], tokens(cache.find_by("user", "carol").unwrap()));
+
+    cache.remove::<&str, Session>("s3").unwrap();
+    assert_eq!(vec!["a"], tokens(cache.find_by("user", "carol").unwrap()));
+    assert_eq!(Some(String::from("a")), cache.take::<_, Session>("s1").unwrap().map(|s| s.token));
+    assert!(cache.find_by::<Session, _>("user", "carol").unwrap().is_empty());
+
+    cache.insert_with("s4", session("dave", 4, "d"), Some(1)).unwrap();
+    assert_eq!(vec!["d"], tokens(cache.find_by("user", "dave").unwrap()));
+
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    assert!(cache.find_by::<Session, _>("user", "dave").unwrap().is_empty());
+
+    // Index sets never share a key with an object, whatever its key and indexed values
+    cache.insert("user:erin", session("erin", 5, "e")).unwrap();
+    cache.insert("s5", session("user:erin\0", 5, "f")).unwrap();
+    assert_eq!(vec!["e"], tokens(cache.find_by("user", "erin").unwrap()));
+    assert_eq!(vec!["f"], tokens(cache.find_by("user", "user:erin\0").unwrap()));
+    assert_eq!(Some(String::from("e")), cache.get::<_, Session>("user:erin").unwrap().map(|s| s.token));
+    cache.remove::<&str, Session>("user:erin").unwrap();
+    cache.remove::<&str, Session>("s5").unwrap();
+}
+
+#[test]
+fn memory_cache_index_test() {
+    let cache = mouscache::memory();
+    index_test(&cache);
+}
+
+#[test]
+fn redis_cache_index_test() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    index_test(&cache);
+}
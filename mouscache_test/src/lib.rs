@@ -14,4 +14,31 @@ mod derive_test;
 mod concurrency_test;
 
 #[cfg(test)]
-mod redis_like_test;
\ No newline at end of file
+mod redis_like_test;
+
+#[cfg(test)]
+mod atomic_test;
+
+#[cfg(test)]
+mod versioned_test;
+
+#[cfg(test)]
+mod field_test;
+
+#[cfg(test)]
+mod ui_test;
+
+#[cfg(test)]
+mod index_test;
+
+#[cfg(test)]
+mod serde_test;
+
+#[cfg(test)]
+mod compression_test;
+
+#[cfg(test)]
+mod encryption_test;
+
+#[cfg(test)]
+mod chunking_test;
@@ -90,4 +90,133 @@ fn test_redis() {
     let res = cache.hash_get::<String>("test_1", "field_2");
     println!("test 5 {:?}", res);
 
-}
\ No newline at end of file
+}
+fn bytes_test(cache: &Cache) {
+    let png: &[u8] = &[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0xff, 0x00];
+
+    let _ = cache.hash_delete("bytes_hash", &["raw", "text"]);
+    assert!(cache.hash_set_bytes("bytes_hash", "raw", png).unwrap());
+    assert!(cache.hash_set("bytes_hash", "text", "hello").unwrap());
+
+    assert_eq!(Some(png.to_vec()), cache.hash_get_bytes("bytes_hash", "raw").unwrap());
+    assert_eq!(Some(b"hello".to_vec()), cache.hash_get_bytes("bytes_hash", "text").unwrap());
+    assert_eq!(None, cache.hash_get_bytes("bytes_hash", "missing").unwrap());
+    assert!(cache.hash_get::<String>("bytes_hash", "raw").is_err());
+
+    let mut values = cache.hash_values_bytes("bytes_hash").unwrap();
+    values.sort();
+    assert_eq!(vec![b"hello".to_vec(), png.to_vec()], values);
+
+    let _ = cache.set_rem_bytes("bytes_set", png);
+    let _ = cache.set_rem("bytes_set", "text");
+    assert!(cache.set_add_bytes("bytes_set", &[png, b"text"]).unwrap());
+    assert!(cache.set_ismember_bytes("bytes_set", png).unwrap());
+    assert!(cache.set_ismember("bytes_set", "text").unwrap());
+
+    let mut members = cache.set_members_bytes("bytes_set").unwrap();
+    members.sort();
+    assert_eq!(vec![b"text".to_vec(), png.to_vec()], members);
+    assert!(cache.set_members("bytes_set").is_err());
+
+    assert!(cache.set_rem_bytes("bytes_set", png).unwrap());
+    assert_eq!(vec![String::from("text")], cache.set_members("bytes_set").unwrap());
+}
+
+#[test]
+fn test_memory_bytes_functions() {
+    let cache = memory();
+    bytes_test(&cache);
+}
+
+#[test]
+fn test_redis_bytes_functions() {
+    let cache = match redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    bytes_test(&cache);
+}
+
+fn typed_test(cache: &Cache) {
+    let _ = cache.hash_delete("typed_hash", &["a", "b", "c"]);
+    let pairs = [("a", "1"), ("b", "2")];
+    assert!(cache.hash_multiple_set("typed_hash", &pairs).unwrap());
+
+    let map: std::collections::HashMap<String, u32> = cache.hash_get_map("typed_hash").unwrap();
+    assert_eq!(2, map.len());
+    assert_eq!(Some(&2), map.get("b"));
+    assert!(cache.hash_get_map::<u32>("typed_hash_missing").unwrap().is_empty());
+
+    assert_eq!(vec![Some(1u32), None, Some(2)], cache.hash_multiple_get_as("typed_hash", &["a", "c", "b"]).unwrap());
+    assert_eq!(vec![None, None], cache.hash_multiple_get_as::<u32>("typed_hash_missing", &["a", "b"]).unwrap());
+    assert!(cache.hash_multiple_get_as::<u32>("typed_hash", &[]).unwrap().is_empty());
+
+    let mut values: Vec<u32> = cache.hash_values_as("typed_hash").unwrap();
+    values.sort();
+    assert_eq!(vec![1, 2], values);
+
+    assert!(cache.hash_set("typed_hash", "c", "three").unwrap());
+    match cache.hash_get_map::<u32>("typed_hash") {
+        Err(CacheError::ParseError(desc)) => {
+            assert!(desc.contains("typed_hash") && desc.contains("field c") && desc.contains("\"three\""), "{}", desc);
+        }
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+    assert!(matches!(cache.hash_get::<u32>("typed_hash", "c"), Err(CacheError::ParseError(_))));
+    assert!(matches!(cache.hash_multiple_get_as::<u32>("typed_hash", &["c"]), Err(CacheError::ParseError(_))));
+    match cache.hash_values_as::<u32>("typed_hash") {
+        Err(CacheError::ParseError(desc)) => assert!(desc.contains("field c") && desc.contains("\"three\""), "{}", desc),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+
+    for key in &["typed_set_1", "typed_set_2"] {
+        for member in cache.set_members(key).unwrap() {
+            cache.set_rem(key, member).unwrap();
+        }
+    }
+    assert!(cache.set_add("typed_set_1", &[1, 2, 3]).unwrap());
+    assert!(cache.set_add("typed_set_2", &[3, 4]).unwrap());
+
+    let sorted = |mut v: Vec<i64>| { v.sort(); v };
+    assert_eq!(vec![1, 2, 3], sorted(cache.set_members_as("typed_set_1").unwrap()));
+    assert_eq!(vec![1, 2], sorted(cache.set_diff_as(&["typed_set_1", "typed_set_2"]).unwrap()));
+    assert_eq!(vec![3], sorted(cache.set_inter_as(&["typed_set_1", "typed_set_2"]).unwrap()));
+    assert_eq!(vec![1, 2, 3, 4], sorted(cache.set_union_as(&["typed_set_1", "typed_set_2"]).unwrap()));
+
+    assert!(cache.set_add("typed_set_2", &["four"]).unwrap());
+    match cache.set_members_as::<i64>("typed_set_2") {
+        Err(CacheError::ParseError(desc)) => assert!(desc.contains("typed_set_2") && desc.contains("\"four\""), "{}", desc),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+    match cache.set_union_as::<i64>(&["typed_set_1", "typed_set_2"]) {
+        Err(CacheError::ParseError(desc)) => assert!(desc.contains("of typed_set_2 from") && desc.contains("\"four\""), "{}", desc),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+    match cache.set_diff_as::<i64>(&["typed_set_2", "typed_set_1"]) {
+        Err(CacheError::ParseError(desc)) => assert!(desc.contains("of typed_set_2 from"), "{}", desc),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_memory_typed_functions() {
+    let cache = memory();
+    typed_test(&cache);
+}
+
+#[test]
+fn test_redis_typed_functions() {
+    let cache = match redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    typed_test(&cache);
+}
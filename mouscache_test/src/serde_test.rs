@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use mouscache;
+use mouscache::Cacheable;
+use serde::{Deserialize, Serialize};
+
+// No `ToString`/`FromStr`, only the serde traits
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(serde = "json")]
+struct Shape {
+    name: String,
+    points: Vec<Point>,
+    center: Option<Point>,
+    #[cache(rename = "meta")]
+    metadata: HashMap<String, u32>,
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(serde = "msgpack", storage = "blob")]
+struct PackedShape {
+    points: Vec<Point>,
+    #[cache(skip)]
+    scratch: u32,
+}
+
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(serde = "bincode")]
+struct Tagged<T> {
+    tag: String,
+    value: T,
+}
+
+// JSON object keys have to be strings
+#[derive(Cacheable, Clone, Debug, PartialEq)]
+#[cache(serde = "json")]
+struct Grid {
+    cells: HashMap<Point, u8>,
+}
+
+fn shape() -> Shape {
+    let mut metadata = HashMap::new();
+    metadata.insert(String::from("sides"), 3);
+    Shape {
+        name: String::from("triangle"),
+        points: vec![Point { x: 0, y: 0 }, Point { x: 4, y: 0 }, Point { x: 0, y: 3 }],
+        center: None,
+        metadata,
+    }
+}
+
+fn packed() -> PackedShape {
+    PackedShape {
+        points: vec![Point { x: -1, y: 1 }],
+        scratch: 0,
+    }
+}
+
+fn tagged() -> Tagged<Point> {
+    Tagged {
+        tag: String::from("origin"),
+        value: Point { x: 0, y: 0 },
+    }
+}
+
+#[test]
+fn memory_cache_serde_test() {
+    let fields: HashMap<String, String> = shape().to_redis_obj().into_iter().collect();
+    assert_eq!("\"triangle\"", fields["name"]);
+    assert_eq!("{\"sides\":3}", fields["meta"]);
+    assert!(!fields.contains_key("center"));
+    assert_eq!(shape(), Shape::from_redis_obj(fields).unwrap());
+
+    let fields: HashMap<String, String> = tagged().to_redis_obj().into_iter().collect();
+    assert_eq!(tagged(), Tagged::from_redis_obj(fields).unwrap());
+
+    let cache = mouscache::memory();
+
+    let _ = cache.insert("shape", shape());
+    let _ = cache.insert("packed", packed());
+    assert_eq!(Some(shape()), cache.get::<_, Shape>("shape").unwrap());
+    assert_eq!(Some(packed()), cache.get::<_, PackedShape>("packed").unwrap());
+}
+
+fn grid() -> Grid {
+    let mut cells = HashMap::new();
+    cells.insert(Point { x: 0, y: 0 }, 1);
+    Grid { cells }
+}
+
+#[test]
+fn serde_unserializable_value_test() {
+    // What the redis cache writes
+    match grid().try_to_redis_obj() {
+        Err(mouscache::CacheError::Other(desc)) => assert!(desc.contains("Unable to serialize value to JSON")),
+        other => panic!("expected an encoding error, got {:?}", other),
+    }
+}
+
+#[test]
+fn redis_cache_serde_test() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.insert("shape", shape());
+    let _ = cache.insert("packed", PackedShape { scratch: 42, ..packed() });
+    let _ = cache.insert("origin", tagged());
+
+    assert_eq!(Some(shape()), cache.get::<_, Shape>("shape").unwrap());
+    assert_eq!(Some(packed()), cache.get::<_, PackedShape>("packed").unwrap());
+    assert_eq!(Some(tagged()), cache.get::<_, Tagged<Point>>("origin").unwrap());
+    assert_eq!(Some(String::from("\"triangle\"")), cache.get_field::<_, Shape>("shape", "name").unwrap());
+
+    // Values the codec can't represent are reported instead of being written
+    let _ = cache.remove::<_, Grid>("grid");
+    assert!(cache.insert("grid", grid()).is_err());
+    assert_eq!(None, cache.get::<_, Grid>("grid").unwrap());
+}
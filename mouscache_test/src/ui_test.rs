@@ -0,0 +1,7 @@
+/// Checks the `#[derive(Cacheable)]` error messages against the `.stderr` files in `ui/`.
+/// Run with `TRYBUILD=overwrite` to update them.
+#[test]
+fn derive_errors_test() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("ui/*.rs");
+}
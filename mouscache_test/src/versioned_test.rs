@@ -0,0 +1,93 @@
+use mouscache;
+use mouscache::CacheError;
+
+#[derive(Cacheable, Clone, Debug)]
+struct Profile {
+    name: String,
+    visits: u32,
+}
+
+#[test]
+fn memory_cache_versioned_test() {
+    let cache = mouscache::memory();
+
+    assert!(cache.get_versioned::<&str, Profile>("profile").unwrap().is_none());
+
+    let version = cache.insert_if_version("profile", Profile { name: String::from("alice"), visits: 0 }, 0).unwrap();
+    assert_eq!(1, version);
+
+    let (mut profile, version) = cache.get_versioned::<&str, Profile>("profile").unwrap().unwrap();
+    assert_eq!(1, version);
+
+    // Another writer sneaks in between our read and our write
+    let _ = cache.insert("profile", Profile { name: String::from("alice"), visits: 10 });
+
+    profile.visits += 1;
+    match cache.insert_if_version("profile", profile.clone(), version) {
+        Err(CacheError::ConflictError(_)) => {}
+        other => panic!("expected a conflict, got {:?}", other),
+    }
+
+    let (_, version) = cache.get_versioned::<&str, Profile>("profile").unwrap().unwrap();
+    assert_eq!(2, version);
+    assert_eq!(3, cache.insert_if_version("profile", profile, version).unwrap());
+
+    let stored: Profile = cache.get("profile").unwrap().unwrap();
+    assert_eq!(1, stored.visits);
+
+    stale_version_test(&cache);
+}
+
+// A version read before the object was removed and written again doesn't match the new object
+fn stale_version_test(cache: &mouscache::Cache) {
+    let _ = cache.insert("stale", Profile { name: String::from("alice"), visits: 0 });
+    let (_, version) = cache.get_versioned::<&str, Profile>("stale").unwrap().unwrap();
+
+    cache.remove::<_, Profile>("stale").unwrap();
+    let _ = cache.insert("stale", Profile { name: String::from("bob"), visits: 0 });
+
+    match cache.insert_if_version("stale", Profile { name: String::from("alice"), visits: 1 }, version) {
+        Err(CacheError::ConflictError(_)) => {}
+        other => panic!("expected a conflict, got {:?}", other),
+    }
+    assert_eq!("bob", cache.get::<_, Profile>("stale").unwrap().unwrap().name);
+    cache.remove::<_, Profile>("stale").unwrap();
+}
+
+#[test]
+fn redis_cache_versioned_test() {
+    let cache = match mouscache::redis("localhost", Some("123456"), None) {
+        Ok(c) => c,
+        Err(e) => {
+            println!("{:?}", e);
+            return;
+        }
+    };
+
+    let _ = cache.remove::<&str, Profile>("profile");
+
+    assert!(cache.get_versioned::<&str, Profile>("profile").unwrap().is_none());
+
+    let first = cache.insert_if_version("profile", Profile { name: String::from("alice"), visits: 0 }, 0).unwrap();
+    assert!(first > 0);
+
+    let (mut profile, version) = cache.get_versioned::<&str, Profile>("profile").unwrap().unwrap();
+    assert_eq!(first, version);
+
+    let _ = cache.insert("profile", Profile { name: String::from("alice"), visits: 10 });
+
+    profile.visits += 1;
+    match cache.insert_if_version("profile", profile.clone(), version) {
+        Err(CacheError::ConflictError(_)) => {}
+        other => panic!("expected a conflict, got {:?}", other),
+    }
+
+    let (_, version) = cache.get_versioned::<&str, Profile>("profile").unwrap().unwrap();
+    assert_ne!(first, version);
+    assert_ne!(version, cache.insert_if_version("profile", profile, version).unwrap());
+
+    let stored: Profile = cache.get("profile").unwrap().unwrap();
+    assert_eq!(1, stored.visits);
+
+    stale_version_test(&cache);
+}
@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate mouscache_derive;
+
+#[derive(Cacheable, Clone)]
+#[cache(expires = "10", expires_field = "ttl")]
+struct Data {
+    ttl: u32,
+}
+
+fn main() {}
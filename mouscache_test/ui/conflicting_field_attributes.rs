@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate mouscache_derive;
+
+#[derive(Cacheable, Clone)]
+struct Data {
+    #[cache(skip, rename = "f")]
+    field: u32,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+#[macro_use]
+extern crate mouscache_derive;
+
+#[derive(Cacheable, Clone)]
+struct Data {
+    #[cache(key)]
+    id: u64,
+    #[cache(key)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#[macro_use]
+extern crate mouscache_derive;
+
+#[derive(Cacheable, Clone)]
+struct Inner {
+    field: u32,
+}
+
+#[derive(Cacheable, Clone)]
+struct Data {
+    #[cache(flatten)]
+    inner: Option<Inner>,
+}
+
+fn main() {}
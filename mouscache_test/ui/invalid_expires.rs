@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate mouscache_derive;
+
+#[derive(Cacheable, Clone)]
+#[cache(expires = "soon")]
+struct Data {
+    field: u32,
+}
+
+fn main() {}
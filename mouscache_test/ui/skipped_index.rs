@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate mouscache_derive;
+
+#[derive(Cacheable, Clone)]
+struct Session {
+    #[cache(skip, index)]
+    user: String,
+}
+
+fn main() {}
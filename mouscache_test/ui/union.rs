@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate mouscache_derive;
+
+#[derive(Cacheable, Clone, Copy)]
+union Data {
+    a: u32,
+    b: f32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate mouscache_derive;
+
+#[derive(Cacheable, Clone)]
+#[cache(expires = "10", color = "red")]
+struct Data {
+    field: u32,
+}
+
+fn main() {}
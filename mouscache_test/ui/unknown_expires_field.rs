@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate mouscache_derive;
+
+#[derive(Cacheable, Clone)]
+#[cache(expires_field = "ttl_secs")]
+struct Data {
+    ttl: u32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate mouscache_derive;
+
+#[derive(Cacheable, Clone)]
+#[cache(storage = "json")]
+struct Data {
+    id: u64,
+}
+
+fn main() {}